@@ -0,0 +1,55 @@
+//! Generates a wall panel of 200x200 hexagons, and times the placement of the
+//! units and the clipping of the holes to the frame.
+//!
+//! ```text
+//! cargo bench --bench panel
+//! cargo bench --bench panel --features rayon
+//! ```
+use std::collections::HashMap;
+use std::time::Instant;
+
+use geo_types::point;
+use polygon_clipping::frame::{Frame, SimpleFrame};
+use polygon_clipping::grid::HoneycombGrid;
+use polygon_clipping::registry::{BaseGeometry, Registry};
+
+const CELLS: usize = 200;
+
+fn main() {
+    let base = BaseGeometry::Rhombus.base(point! {x: 0., y: 0.}, 4.);
+    let unit = Registry::default()
+        .build("hexagon", &base, &HashMap::new())
+        .unwrap()
+        .polygon;
+    let (dx, dy) = (base.u.x(), 3. * base.u.y());
+    let width = (2 * CELLS - 2) as f64 * dx;
+    let height = (CELLS - 1) as f64 * dy;
+    let frame = SimpleFrame::new(point! {x: 0., y: 0.}, width, height, 1.0);
+
+    let parallel = if cfg!(feature = "rayon") {
+        "in parallel"
+    } else {
+        "sequentially"
+    };
+    println!("{CELLS}x{CELLS} cells, {parallel}");
+
+    let start = Instant::now();
+    let mut grid = HoneycombGrid::new(dx, dy, CELLS, CELLS);
+    let interiors = grid.fill_with_unit(&unit);
+    let filled = start.elapsed();
+    println!(
+        "fill_with_unit: {} holes in {:.3} s",
+        interiors.len(),
+        filled.as_secs_f64()
+    );
+
+    let start = Instant::now();
+    let figure = frame.frame(&interiors);
+    let framed = start.elapsed();
+    println!(
+        "frame: {} holes in {:.3} s",
+        figure.interiors().len(),
+        framed.as_secs_f64()
+    );
+    println!("total: {:.3} s", (filled + framed).as_secs_f64());
+}
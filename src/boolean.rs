@@ -0,0 +1,296 @@
+use geo::orient::{Direction, Orient};
+use geo::BooleanOps;
+use geo_clipper::{Clipper, ClipperOpen};
+use geo_types::{MultiLineString, MultiPolygon, Polygon};
+
+/// Boolean operations on polygons, and clipping of lines by polygons.
+///
+/// The polygons of a multipolygon may overlap, and their rings may be wound
+/// either way: the interiors are always holes.
+///
+/// The scale is that of the [GeometryContext](crate::precision::GeometryContext):
+/// the backends working on an integer grid round the coordinates with it, the
+/// others ignore it.
+///
+/// The offsets are not part of it: geo 0.28 has none, so the corners, the tool
+/// paths of the G-code, the merge of the units and the growing of the holes by
+/// the splitter offset with Clipper, whatever the backend.  The tests also
+/// check the figures against Clipper directly.
+pub trait BooleanBackend {
+    fn intersection(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon;
+    fn union(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon;
+    fn difference(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon;
+    /// The parts of the lines inside the polygon, or outside of it when `invert`
+    fn clip(
+        &self,
+        polygon: &MultiPolygon,
+        lines: &MultiLineString,
+        invert: bool,
+        scale: f64,
+    ) -> MultiLineString;
+}
+
+/// Clipper, through geo-clipper: the coordinates are rounded to the grid of
+/// the scale, and the operations are exact on it.  Its fill rule needs the
+/// holes wound the other way round than the exteriors.
+pub struct ClipperBackend;
+
+/// The boolean operations of geo, in floating point.  They need polygons that
+/// do not overlap: those of a multipolygon are merged first.
+pub struct GeoBackend;
+
+impl BooleanBackend for ClipperBackend {
+    fn intersection(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon {
+        let (a, b) = (a.orient(Direction::Default), b.orient(Direction::Default));
+        Clipper::intersection(&a, &b, scale)
+    }
+    fn union(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon {
+        let (a, b) = (a.orient(Direction::Default), b.orient(Direction::Default));
+        Clipper::union(&a, &b, scale)
+    }
+    fn difference(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon {
+        let (a, b) = (a.orient(Direction::Default), b.orient(Direction::Default));
+        Clipper::difference(&a, &b, scale)
+    }
+    fn clip(
+        &self,
+        polygon: &MultiPolygon,
+        lines: &MultiLineString,
+        invert: bool,
+        scale: f64,
+    ) -> MultiLineString {
+        let polygon = polygon.orient(Direction::Default);
+        if invert {
+            ClipperOpen::difference(lines, &polygon, scale)
+        } else {
+            ClipperOpen::intersection(lines, &polygon, scale)
+        }
+    }
+}
+
+/// Merges the polygons of a multipolygon that overlap, and the holes of a
+/// polygon that touch
+fn merged(polygons: &MultiPolygon) -> MultiPolygon {
+    if let [polygon] = polygons.0.as_slice() {
+        if polygon.interiors().is_empty() {
+            return polygons.clone();
+        }
+    }
+    let union = |rings: Vec<Polygon>| {
+        rings
+            .into_iter()
+            .fold(MultiPolygon::new(vec![]), |merged, p| {
+                BooleanOps::union(&merged, &MultiPolygon::from(p))
+            })
+    };
+    union(
+        polygons
+            .iter()
+            .flat_map(|p| {
+                let exterior = MultiPolygon::from(Polygon::new(p.exterior().clone(), vec![]));
+                if p.interiors().is_empty() {
+                    return exterior.0;
+                }
+                let holes = p
+                    .interiors()
+                    .iter()
+                    .map(|h| Polygon::new(h.clone(), vec![]))
+                    .collect();
+                BooleanOps::difference(&exterior, &union(holes)).0
+            })
+            .collect(),
+    )
+}
+
+impl BooleanBackend for GeoBackend {
+    fn intersection(&self, a: &MultiPolygon, b: &MultiPolygon, _scale: f64) -> MultiPolygon {
+        BooleanOps::intersection(&merged(a), &merged(b))
+    }
+    fn union(&self, a: &MultiPolygon, b: &MultiPolygon, _scale: f64) -> MultiPolygon {
+        BooleanOps::union(&merged(a), &merged(b))
+    }
+    fn difference(&self, a: &MultiPolygon, b: &MultiPolygon, _scale: f64) -> MultiPolygon {
+        BooleanOps::difference(&merged(a), &merged(b))
+    }
+    fn clip(
+        &self,
+        polygon: &MultiPolygon,
+        lines: &MultiLineString,
+        invert: bool,
+        _scale: f64,
+    ) -> MultiLineString {
+        BooleanOps::clip(&merged(polygon), lines, invert)
+    }
+}
+
+/// The backend of the boolean operations, picked at runtime with
+/// `GeometryContext::with_backend`.  The default one is Clipper; geo is only
+/// used when asked for.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    #[default]
+    Clipper,
+    Geo,
+}
+
+impl BooleanBackend for Backend {
+    fn intersection(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon {
+        match self {
+            Backend::Clipper => ClipperBackend.intersection(a, b, scale),
+            Backend::Geo => GeoBackend.intersection(a, b, scale),
+        }
+    }
+    fn union(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon {
+        match self {
+            Backend::Clipper => ClipperBackend.union(a, b, scale),
+            Backend::Geo => GeoBackend.union(a, b, scale),
+        }
+    }
+    fn difference(&self, a: &MultiPolygon, b: &MultiPolygon, scale: f64) -> MultiPolygon {
+        match self {
+            Backend::Clipper => ClipperBackend.difference(a, b, scale),
+            Backend::Geo => GeoBackend.difference(a, b, scale),
+        }
+    }
+    fn clip(
+        &self,
+        polygon: &MultiPolygon,
+        lines: &MultiLineString,
+        invert: bool,
+        scale: f64,
+    ) -> MultiLineString {
+        match self {
+            Backend::Clipper => ClipperBackend.clip(polygon, lines, invert, scale),
+            Backend::Geo => GeoBackend.clip(polygon, lines, invert, scale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::operations::{intersect_with, BoundaryPolicy};
+    use crate::precision::GeometryContext;
+    use crate::split::{Connector, PanelSplitter};
+    use crate::test_support::{assert_valid, nothing};
+    use crate::test_support::{make_base, make_hexagon_config};
+    use geo::{Area, EuclideanLength};
+    use geo_types::{coord, point, LineString, Polygon};
+
+    /// Prints the areas found by both backends, and returns their difference
+    fn report(name: &str, clipper: f64, geo: f64) -> f64 {
+        let difference = (clipper - geo).abs();
+        println!("{name}: clipper {clipper:.6}, geo {geo:.6}, difference {difference:.2e}");
+        difference
+    }
+
+    /// Frames the hexagon panel, the frame crossing the hexagons
+    fn frame_panel(backend: Backend) -> Polygon {
+        let base = make_base();
+        let hexa = Hexagon::new_at_base(&base, &make_hexagon_config());
+        let (dx, dy) = (base.u.x(), 3. * base.u.y());
+        let (nx, ny) = (4, 5);
+        let interiors = HoneycombGrid::new(dx, dy, nx, ny).fill_with_unit(&hexa.polygon);
+        let width = (2 * nx - 2) as f64 * dx - 0.5;
+        let frame = SimpleFrame::new(point! {x: 0.3, y: 0.3}, width, (ny - 1) as f64 * dy, 1.);
+        let contour = frame.outer_contour();
+        let context = GeometryContext::for_geometry(&contour).with_backend(backend);
+        let inner = frame.inner_contour();
+        intersect_with(&interiors, contour, inner, &context, BoundaryPolicy::Keep)
+    }
+
+    #[test]
+    fn framed_panels() {
+        let clipper = frame_panel(Backend::Clipper);
+        let geo = frame_panel(Backend::Geo);
+        assert_valid(&clipper, nothing);
+        assert_valid(&geo, nothing);
+
+        assert_eq!(clipper.interiors().len(), geo.interiors().len());
+        // The holes are rounded by Clipper to a hundredth of micrometre
+        let perimeter: f64 = clipper
+            .interiors()
+            .iter()
+            .map(|h| h.euclidean_length())
+            .sum();
+        let difference = report("framed", clipper.unsigned_area(), geo.unsigned_area());
+        assert!(difference < perimeter * 1e-5);
+    }
+
+    /// A plate with a grid of square holes
+    fn squares_panel() -> Polygon {
+        let square = |x: f64, y: f64| {
+            LineString::from(vec![(x, y), (x + 1.5, y), (x + 1.5, y + 1.5), (x, y + 1.5)])
+        };
+        Polygon::new(
+            LineString::from(vec![(0., 0.), (20.5, 0.), (20.5, 20.5), (0., 20.5)]),
+            (0..100)
+                .map(|i| square(0.5 + 2. * (i % 10) as f64, 0.5 + 2. * (i / 10) as f64))
+                .collect(),
+        )
+    }
+
+    /// Splits a panel with both backends and reports the areas of the pieces
+    fn split_both(panel: &Polygon, splitter: &PanelSplitter) {
+        let clipper = splitter.split_with(panel, Backend::Clipper).unwrap();
+        let geo = splitter.split_with(panel, Backend::Geo).unwrap();
+
+        assert_eq!(clipper.len(), geo.len());
+        let scale = GeometryContext::for_geometry(panel).scale;
+        for (c, g) in clipper.iter().zip(geo.iter()) {
+            assert_eq!(c.label(), g.label());
+            let name = format!("piece {}", c.label());
+            let difference = report(&name, c.shape.unsigned_area(), g.shape.unsigned_area());
+            // Clipper rounds every ring of the piece to its grid
+            let rings: f64 = c
+                .shape
+                .iter()
+                .flat_map(|p| p.interiors().iter().chain([p.exterior()]))
+                .map(|r| r.euclidean_length())
+                .sum();
+            assert!(difference < rings / scale);
+        }
+    }
+
+    #[test]
+    fn split_panels() {
+        let splitter = PanelSplitter::new(12., 12., 0.5, Connector::Dovetail(0.3));
+        split_both(&squares_panel(), &splitter);
+
+        let panel = frame_panel(Backend::Clipper);
+        let splitter = PanelSplitter::new(12., 12., 0.15, Connector::Dovetail(0.3));
+        split_both(&panel, &splitter);
+    }
+
+    #[test]
+    fn clipped_lines() {
+        // A triangle with a hole, and a grid of lines across it.  The lines
+        // along an edge are kept by Clipper, and not by geo: they miss the edges.
+        let triangle = MultiPolygon::from(Polygon::new(
+            LineString::from(vec![(1., 1.), (7., 1.), (4., 6.)]),
+            vec![LineString::from(vec![(2., 1.5), (6., 1.5), (4., 5.)])],
+        ));
+        let lines = MultiLineString::new(
+            (0..=10)
+                .flat_map(|i| {
+                    let i = i as f64 + 0.25;
+                    [
+                        LineString::new(vec![coord! {x: 0., y: i}, coord! {x: 10., y: i}]),
+                        LineString::new(vec![coord! {x: i, y: 0.}, coord! {x: i, y: 10.}]),
+                    ]
+                })
+                .collect(),
+        );
+
+        for invert in [false, true] {
+            let clipper = ClipperBackend.clip(&triangle, &lines, invert, 1e5);
+            let geo = GeoBackend.clip(&triangle, &lines, invert, 1e5);
+            let difference = (clipper.euclidean_length() - geo.euclidean_length()).abs();
+            println!("lines, inverted {invert}: difference of length {difference:.2e}");
+            assert!(difference < 1e-3);
+        }
+    }
+}
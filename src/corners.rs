@@ -0,0 +1,295 @@
+use geo::orient::{Direction, Orient};
+use geo::ConvexHull;
+use geo_clipper::{Clipper, EndType, JoinType};
+use geo_types::{Coord, LineString, MultiPoint, MultiPolygon, Point, Polygon};
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::boolean::BooleanBackend;
+use crate::precision::GeometryContext;
+
+/// Number of segments of the circles of the reliefs
+const CIRCLE_SEGMENTS: usize = 48;
+
+/// Turns flatter than this are not treated as corners
+const EPSILON: f64 = 1e-9;
+
+/// How the inside corners that a round tool can't reach are cut.
+///
+/// ```text
+///    dogbone        T-bone         fillet
+///
+///   |              |              |
+///   |   hole       |   hole       |   hole
+///  ,|              |              |
+/// ( +------       (+------         \______
+///  `               `
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CornerStyle {
+    /// The tool goes into the corner along its bisector until it touches the vertex
+    Dogbone,
+    /// The tool goes into the corner along the longer side until it touches the vertex
+    TBone,
+    /// The corner is left rounded with the radius of the tool
+    Fillet,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CornerWarning {
+    /// The hole with the given index is too small for the tool, it is left as it is
+    HoleTooSmall { hole: usize },
+}
+
+impl fmt::Display for CornerWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CornerWarning::HoleTooSmall { hole } => {
+                write!(f, "hole {} is too small for the tool", hole)
+            }
+        }
+    }
+}
+
+/// A polygon with its inside corners treated for a tool
+pub struct TreatedPolygon {
+    pub polygon: MultiPolygon,
+    pub warnings: Vec<CornerWarning>,
+}
+
+/// Treats the inside corners of a figure for a CNC router.
+///
+/// The inside corners are the convex corners of the holes, and the concave corners
+/// of the exterior: those where the material is on the outside of the turn.
+pub struct CornerTreatment {
+    tool_diameter: f64,
+    style: CornerStyle,
+}
+
+impl CornerTreatment {
+    pub fn new(tool_diameter: f64, style: CornerStyle) -> Self {
+        CornerTreatment {
+            tool_diameter,
+            style,
+        }
+    }
+
+    fn radius(&self) -> f64 {
+        self.tool_diameter / 2.
+    }
+
+    /// Treats every ring of a polygon.  The holes the tool can't go into are kept
+    /// as they are, with a warning.  The precision is chosen from the extent of
+    /// the polygon.
+    pub fn apply(&self, polygon: &Polygon) -> TreatedPolygon {
+        // Exterior counter-clockwise and holes clockwise: the material is always
+        // on the left of a ring
+        let polygon = polygon.orient(Direction::Default);
+        let context = &GeometryContext::for_geometry(&polygon);
+        let mut warnings = Vec::new();
+
+        let mut holes: Vec<Polygon> = Vec::new();
+        for (index, hole) in polygon.interiors().iter().enumerate() {
+            let region = Polygon::new(hole.clone(), vec![]).orient(Direction::Default);
+            if self.too_small(&region, context) {
+                warnings.push(CornerWarning::HoleTooSmall { hole: index });
+                holes.push(region);
+                continue;
+            }
+            match self.style {
+                CornerStyle::Fillet => holes.extend(self.opening(&region, context)),
+                _ => {
+                    holes.push(region);
+                    holes.extend(self.reliefs(hole));
+                }
+            }
+        }
+
+        let exterior = Polygon::new(polygon.exterior().clone(), vec![]);
+        let material = match self.style {
+            CornerStyle::Fillet => self.closing(&exterior, context),
+            _ => context.backend.difference(
+                &exterior.into(),
+                &MultiPolygon::new(self.reliefs(polygon.exterior())),
+                context.scale,
+            ),
+        };
+        TreatedPolygon {
+            polygon: context.backend.difference(
+                &material,
+                &MultiPolygon::new(holes),
+                context.scale,
+            ),
+            warnings,
+        }
+    }
+
+    /// Whether the tool does not fit anywhere in a hole
+    fn too_small(&self, region: &Polygon, context: &GeometryContext) -> bool {
+        region
+            .offset(
+                -self.radius(),
+                JoinType::Miter(2.),
+                EndType::ClosedPolygon,
+                context.scale,
+            )
+            .0
+            .is_empty()
+    }
+
+    /// What the tool can cut out of a region: the region shrunk, then grown back,
+    /// by the radius of the tool
+    fn opening(&self, region: &Polygon, context: &GeometryContext) -> MultiPolygon {
+        let round = context.round();
+        region
+            .offset(-self.radius(), round, EndType::ClosedPolygon, context.scale)
+            .offset(self.radius(), round, EndType::ClosedPolygon, context.scale)
+    }
+
+    /// What the tool leaves of the material: the material grown, then shrunk, by
+    /// the radius of the tool
+    fn closing(&self, material: &Polygon, context: &GeometryContext) -> MultiPolygon {
+        let round = context.round();
+        material
+            .offset(self.radius(), round, EndType::ClosedPolygon, context.scale)
+            .offset(-self.radius(), round, EndType::ClosedPolygon, context.scale)
+    }
+
+    /// The reliefs of the inside corners of a ring, with the material on its left.
+    ///
+    /// A relief is the area swept by the tool from where it stops in the corner,
+    /// touching both sides, to where it touches the vertex.
+    fn reliefs(&self, ring: &LineString) -> Vec<Polygon> {
+        let r = self.radius();
+        let mut coords: Vec<Coord> = ring.0.clone();
+        if ring.is_closed() {
+            coords.pop();
+        }
+        let n = coords.len();
+        let mut reliefs = Vec::new();
+        for i in 0..n {
+            let (prev, p, next) = (coords[(i + n - 1) % n], coords[i], coords[(i + 1) % n]);
+            let (la, lb) = (length(p - prev), length(next - p));
+            if la < EPSILON || lb < EPSILON {
+                continue;
+            }
+            let a = (p - prev) / la;
+            let b = (next - p) / lb;
+            // A turn to the right is an inside corner, as the material is on the left
+            let cross = a.x * b.y - a.y * b.x;
+            if cross > -EPSILON {
+                continue;
+            }
+            let bisector = (b - a) / length(b - a);
+            // Half of the angle of the corner
+            let sin_half = length(a + b) / 2.;
+            let stop = p + bisector * (r / sin_half.max(EPSILON));
+            let touch = match self.style {
+                CornerStyle::Dogbone => p + bisector * r,
+                // The normal of the longer side, on the right
+                _ if la >= lb => p + right_normal(a) * r,
+                _ => p + right_normal(b) * r,
+            };
+            reliefs.push(swept_circle(stop, touch, r));
+        }
+        reliefs
+    }
+}
+
+fn length(c: Coord) -> f64 {
+    (c.x * c.x + c.y * c.y).sqrt()
+}
+
+fn right_normal(c: Coord) -> Coord {
+    Coord { x: c.y, y: -c.x }
+}
+
+/// Area swept by a circle moving from one center to another
+fn swept_circle(from: Coord, to: Coord, r: f64) -> Polygon {
+    let points: Vec<Point> = [from, to]
+        .iter()
+        .flat_map(|c| {
+            (0..CIRCLE_SEGMENTS).map(move |k| {
+                let angle = 2. * PI * k as f64 / CIRCLE_SEGMENTS as f64;
+                Point::new(c.x + r * angle.cos(), c.y + r * angle.sin())
+            })
+        })
+        .collect();
+    MultiPoint::new(points).convex_hull()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kumiko::KumikoFigure;
+    use crate::svg::save_polygons_as_svg;
+    use crate::test_support::{assert_valid, nothing};
+    use crate::test_support::{make_triskell_base, make_triskell_config};
+    use crate::triskell::Triskell;
+    use geo::{Area, Contains};
+    use geo_types::{coord, point, polygon};
+
+    fn square_with_hole() -> Polygon {
+        polygon!(
+            exterior: [(x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.), (x: 0., y: 10.)],
+            interiors: [[(x: 2., y: 2.), (x: 6., y: 2.), (x: 6., y: 6.), (x: 2., y: 6.)]],
+        )
+    }
+
+    #[test]
+    fn dogbone_reaches_corners() {
+        let treated = CornerTreatment::new(0.5, CornerStyle::Dogbone).apply(&square_with_hole());
+
+        assert!(treated.warnings.is_empty());
+        assert_eq!(1, treated.polygon.0.len());
+        let polygon = &treated.polygon.0[0];
+        assert_eq!(1, polygon.interiors().len());
+        // The material along the sides, next to the corners of the hole, is cut away
+        for beside in [point! {x: 1.98, y: 2.1}, point! {x: 6.02, y: 5.9}] {
+            assert!(square_with_hole().contains(&beside));
+            assert!(!polygon.contains(&beside));
+        }
+        // The convex corners of the exterior are left alone
+        assert!(polygon.exterior().0.contains(&coord! {x: 0., y: 0.}));
+        assert_valid(polygon, nothing);
+    }
+
+    #[test]
+    fn tbone_and_fillet_areas() {
+        let square = square_with_hole();
+        let r: f64 = 0.25;
+        let tbone = CornerTreatment::new(2. * r, CornerStyle::TBone).apply(&square);
+        let fillet = CornerTreatment::new(2. * r, CornerStyle::Fillet).apply(&square);
+
+        // Each T-bone cuts half a circle out of the material
+        let expected = 100. - 16. - 4. * PI * r * r / 2.;
+        assert!((tbone.polygon.unsigned_area() - expected).abs() < 1e-2);
+        // Each fillet leaves a square corner less a quarter of a circle
+        let expected = 100. - 16. + 4. * (r * r - PI * r * r / 4.);
+        assert!((fillet.polygon.unsigned_area() - expected).abs() < 1e-2);
+    }
+
+    #[test]
+    fn treat_triskell() {
+        let base = make_triskell_base();
+        let triskell = Triskell::new_at_base(&base, &make_triskell_config());
+        let holes = triskell.polygon().interiors().len();
+
+        let treated = CornerTreatment::new(0.1, CornerStyle::Dogbone).apply(triskell.polygon());
+        assert!(treated.warnings.is_empty());
+        assert_eq!(holes, treated.polygon.0[0].interiors().len());
+        assert!(treated.polygon.unsigned_area() < triskell.polygon().unsigned_area());
+        save_polygons_as_svg(&treated.polygon.0, "test_figures/triskell_dogbone.svg");
+        treated
+            .polygon
+            .iter()
+            .for_each(|p| assert_valid(p, nothing));
+
+        // A tool wider than the holes can't cut any of them
+        let treated = CornerTreatment::new(3., CornerStyle::Fillet).apply(triskell.polygon());
+        let expected: Vec<CornerWarning> = (0..holes)
+            .map(|hole| CornerWarning::HoleTooSmall { hole })
+            .collect();
+        assert_eq!(expected, treated.warnings);
+    }
+}
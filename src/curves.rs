@@ -0,0 +1,546 @@
+use geo::Area;
+use geo_types::{coord, Coord, LineString, Polygon};
+use std::f64::consts::PI;
+
+/// Deepest subdivision of a bezier, when it is flattened or offset
+const MAX_DEPTH: u32 = 16;
+
+/// Tolerance of the flattening done to find the orientation of a path
+const ORIENTATION_TOLERANCE: f64 = 1e-2;
+
+/// A piece of a curve path
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment {
+    Line {
+        from: Coord,
+        to: Coord,
+    },
+    /// An arc of circle, from the angle `start`, counterclockwise if `sweep` is positive
+    Arc {
+        center: Coord,
+        radius: f64,
+        start: f64,
+        sweep: f64,
+    },
+    /// A cubic bezier, pulled by its two control points
+    Cubic {
+        from: Coord,
+        ctrl1: Coord,
+        ctrl2: Coord,
+        to: Coord,
+    },
+}
+
+fn norm(v: Coord) -> f64 {
+    (v.x * v.x + v.y * v.y).sqrt()
+}
+
+fn polar(center: Coord, radius: f64, angle: f64) -> Coord {
+    center + coord! {x: radius * angle.cos(), y: radius * angle.sin()}
+}
+
+/// The unit vector normal to `v`, on its right, as `InfiniteLine::shift_by` moves lines
+fn right_normal(v: Coord) -> Coord {
+    let n = norm(v);
+    coord! {x: v.y / n, y: -v.x / n}
+}
+
+/// Distance from a point to the segment from `a` to `b`
+fn distance_to_segment(p: Coord, a: Coord, b: Coord) -> f64 {
+    let ab = b - a;
+    let length2 = ab.x * ab.x + ab.y * ab.y;
+    if length2 == 0. {
+        return norm(p - a);
+    }
+    let ap = p - a;
+    let t = ((ap.x * ab.x + ap.y * ab.y) / length2).clamp(0., 1.);
+    norm(p - (a + ab * t))
+}
+
+fn cubic_at(p: &[Coord; 4], t: f64) -> Coord {
+    let s = 1. - t;
+    p[0] * (s * s * s) + p[1] * (3. * s * s * t) + p[2] * (3. * s * t * t) + p[3] * (t * t * t)
+}
+
+/// Derivative of a cubic bezier.  Where it vanishes, as at an end whose control
+/// point is on the end, the direction of the chord is used.
+fn cubic_tangent(p: &[Coord; 4], t: f64) -> Coord {
+    let s = 1. - t;
+    let d =
+        (p[1] - p[0]) * (3. * s * s) + (p[2] - p[1]) * (6. * s * t) + (p[3] - p[2]) * (3. * t * t);
+    if norm(d) > 1e-12 {
+        d
+    } else if t < 0.5 {
+        if norm(p[2] - p[0]) > 1e-12 {
+            p[2] - p[0]
+        } else {
+            p[3] - p[0]
+        }
+    } else if norm(p[3] - p[1]) > 1e-12 {
+        p[3] - p[1]
+    } else {
+        p[3] - p[0]
+    }
+}
+
+/// Splits a cubic bezier in two halves (de Casteljau)
+fn split_cubic(p: &[Coord; 4]) -> ([Coord; 4], [Coord; 4]) {
+    let mid = |a: Coord, b: Coord| (a + b) / 2.;
+    let (p01, p12, p23) = (mid(p[0], p[1]), mid(p[1], p[2]), mid(p[2], p[3]));
+    let (p012, p123) = (mid(p01, p12), mid(p12, p23));
+    let m = mid(p012, p123);
+    ([p[0], p01, p012, m], [m, p123, p23, p[3]])
+}
+
+/// Replaces a cubic with lines until its control points are within the
+/// tolerance of the chord, which holds the curve in their convex hull
+fn flatten_cubic(p: &[Coord; 4], tolerance: f64, depth: u32, points: &mut Vec<Coord>) {
+    let flatness = distance_to_segment(p[1], p[0], p[3]).max(distance_to_segment(p[2], p[0], p[3]));
+    if flatness <= tolerance || depth == MAX_DEPTH {
+        points.push(p[3]);
+        return;
+    }
+    let (a, b) = split_cubic(p);
+    flatten_cubic(&a, tolerance, depth + 1, points);
+    flatten_cubic(&b, tolerance, depth + 1, points);
+}
+
+/// Offsets a cubic by moving each end and its control point along the normal
+/// of this end, which keeps the tangents.  Where the result strays from the
+/// true offset curve by more than the tolerance, the cubic is split in two.
+fn offset_cubic(p: &[Coord; 4], d: f64, tolerance: f64, depth: u32, segments: &mut Vec<Segment>) {
+    let n0 = right_normal(cubic_tangent(p, 0.)) * d;
+    let n3 = right_normal(cubic_tangent(p, 1.)) * d;
+    let q = [p[0] + n0, p[1] + n0, p[2] + n3, p[3] + n3];
+    let error = [0.25, 0.5, 0.75]
+        .iter()
+        .map(|&t| {
+            let exact = cubic_at(p, t) + right_normal(cubic_tangent(p, t)) * d;
+            norm(cubic_at(&q, t) - exact)
+        })
+        .fold(0., f64::max);
+    if error <= tolerance || depth == MAX_DEPTH {
+        segments.push(Segment::Cubic {
+            from: q[0],
+            ctrl1: q[1],
+            ctrl2: q[2],
+            to: q[3],
+        });
+        return;
+    }
+    let (a, b) = split_cubic(p);
+    offset_cubic(&a, d, tolerance, depth + 1, segments);
+    offset_cubic(&b, d, tolerance, depth + 1, segments);
+}
+
+impl Segment {
+    pub fn start(&self) -> Coord {
+        match *self {
+            Segment::Line { from, .. } | Segment::Cubic { from, .. } => from,
+            Segment::Arc {
+                center,
+                radius,
+                start,
+                ..
+            } => polar(center, radius, start),
+        }
+    }
+
+    pub fn end(&self) -> Coord {
+        match *self {
+            Segment::Line { to, .. } | Segment::Cubic { to, .. } => to,
+            Segment::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => polar(center, radius, start + sweep),
+        }
+    }
+
+    /// The points of the lines replacing the segment, from the one after its
+    /// start to its end.  No point of the segment is farther than the
+    /// tolerance from the lines.
+    ///
+    /// An arc is cut in equal steps, as long as its sagitta is within the
+    /// tolerance; a bezier is split in halves until it is flat enough.  Either
+    /// one gives at most as many lines as `MAX_DEPTH` halvings, when the
+    /// tolerance is zero or too small.
+    pub fn flatten(&self, tolerance: f64) -> Vec<Coord> {
+        match *self {
+            Segment::Line { to, .. } => vec![to],
+            Segment::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let step = if tolerance < radius {
+                    2. * (1. - tolerance / radius).acos()
+                } else {
+                    PI / 2.
+                };
+                let n = (sweep.abs() / step)
+                    .ceil()
+                    .max(1.)
+                    .min((1 << MAX_DEPTH) as f64) as usize;
+                (1..=n)
+                    .map(|i| polar(center, radius, start + sweep * i as f64 / n as f64))
+                    .collect()
+            }
+            Segment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let mut points = vec![];
+                flatten_cubic(&[from, ctrl1, ctrl2, to], tolerance, 0, &mut points);
+                points
+            }
+        }
+    }
+
+    /// Shifts the segment by a given distance, positive to the right, as
+    /// `InfiniteLine::shift_by` does.  A bezier may give several beziers, to
+    /// stay within the tolerance of the true offset curve.
+    pub fn shift_by(&self, d: f64, tolerance: f64) -> Vec<Segment> {
+        match *self {
+            Segment::Line { from, to } => {
+                let n = right_normal(to - from) * d;
+                vec![Segment::Line {
+                    from: from + n,
+                    to: to + n,
+                }]
+            }
+            // Counterclockwise, the right is outside
+            Segment::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => vec![Segment::Arc {
+                center,
+                radius: radius + d * sweep.signum(),
+                start,
+                sweep,
+            }],
+            Segment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let mut segments = vec![];
+                offset_cubic(&[from, ctrl1, ctrl2, to], d, tolerance, 0, &mut segments);
+                segments
+            }
+        }
+    }
+
+    /// The segment as beziers, for the formats that only know those.  A line
+    /// is a bezier with its control points on the line; an arc is cut in
+    /// pieces of at most a quarter turn, each one close to a bezier.
+    pub fn to_cubics(&self) -> Vec<Segment> {
+        match *self {
+            Segment::Line { from, to } => vec![Segment::Cubic {
+                from,
+                ctrl1: from + (to - from) / 3.,
+                ctrl2: from + (to - from) * (2. / 3.),
+                to,
+            }],
+            Segment::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let n = (sweep.abs() / (PI / 2.)).ceil().max(1.) as usize;
+                let step = sweep / n as f64;
+                // Length of the control arms of a bezier close to an arc of this step
+                let k = 4. / 3. * (step / 4.).tan() * radius;
+                (0..n)
+                    .map(|i| {
+                        let (a, b) = (start + step * i as f64, start + step * (i + 1) as f64);
+                        let (from, to) = (polar(center, radius, a), polar(center, radius, b));
+                        Segment::Cubic {
+                            from,
+                            ctrl1: from + coord! {x: -a.sin(), y: a.cos()} * k,
+                            ctrl2: to - coord! {x: -b.sin(), y: b.cos()} * k,
+                            to,
+                        }
+                    })
+                    .collect()
+            }
+            Segment::Cubic { .. } => vec![*self],
+        }
+    }
+}
+
+/// Segments following each other; a closed path ends where it starts
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurvePath {
+    pub segments: Vec<Segment>,
+}
+
+impl CurvePath {
+    pub fn new(segments: Vec<Segment>) -> Self {
+        CurvePath { segments }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        match (self.segments.first(), self.segments.last()) {
+            (Some(first), Some(last)) => norm(first.start() - last.end()) < 1e-9,
+            _ => false,
+        }
+    }
+
+    /// A closed path turning clockwise, as the holes of the polygons oriented
+    /// by `orient(Direction::Default)`
+    pub fn is_hole(&self) -> bool {
+        self.is_closed() && self.to_polygon(ORIENTATION_TOLERANCE).signed_area() < 0.
+    }
+
+    pub fn to_line_string(&self, tolerance: f64) -> LineString {
+        let mut coords: Vec<Coord> = self
+            .segments
+            .first()
+            .map(|s| s.start())
+            .into_iter()
+            .collect();
+        for segment in self.segments.iter() {
+            coords.extend(segment.flatten(tolerance));
+        }
+        LineString::new(coords)
+    }
+
+    /// The polygon of a closed path, which can then be clipped and framed
+    pub fn to_polygon(&self, tolerance: f64) -> Polygon {
+        Polygon::new(self.to_line_string(tolerance), vec![])
+    }
+
+    /// Shifts the path by a given distance, positive to the right.
+    ///
+    /// Two lines meeting at a corner are extended to meet again, as the lines
+    /// of `LinesLR` do; other segments that do not meet any more are joined by
+    /// a line.
+    pub fn shift_by(&self, d: f64, tolerance: f64) -> CurvePath {
+        let mut segments: Vec<Segment> = vec![];
+        for segment in self.segments.iter() {
+            let shifted = segment.shift_by(d, tolerance);
+            if let Some(last) = segments.pop() {
+                segments.extend(join(last, shifted[0]));
+                segments.extend(shifted.into_iter().skip(1));
+            } else {
+                segments.extend(shifted);
+            }
+        }
+        if self.is_closed() && segments.len() > 1 {
+            let last = segments.pop().unwrap();
+            let mut joined = join(last, segments[0]);
+            segments[0] = joined.pop().unwrap();
+            segments.extend(joined);
+        }
+        CurvePath { segments }
+    }
+
+    /// The `d` attribute of an svg path, keeping the arcs and the beziers
+    pub fn svg_path_data(&self) -> String {
+        let mut data = match self.segments.first() {
+            Some(first) => format!("M{} {}", first.start().x, first.start().y),
+            None => return String::new(),
+        };
+        for segment in self.segments.iter() {
+            match *segment {
+                Segment::Line { to, .. } => data.push_str(&format!("L{} {}", to.x, to.y)),
+                Segment::Arc {
+                    center,
+                    radius,
+                    start,
+                    sweep,
+                } => {
+                    // A full circle can't be a single arc of svg
+                    let n = if sweep.abs() > PI { 2 } else { 1 };
+                    for i in 1..=n {
+                        let to = polar(center, radius, start + sweep * i as f64 / n as f64);
+                        let large = if sweep.abs() / n as f64 > PI { 1 } else { 0 };
+                        let positive = if sweep > 0. { 1 } else { 0 };
+                        data.push_str(&format!(
+                            "A{} {} 0 {} {} {} {}",
+                            radius, radius, large, positive, to.x, to.y
+                        ));
+                    }
+                }
+                Segment::Cubic {
+                    ctrl1, ctrl2, to, ..
+                } => data.push_str(&format!(
+                    "C{} {} {} {} {} {}",
+                    ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y
+                )),
+            }
+        }
+        if self.is_closed() {
+            data.push('Z');
+        }
+        data
+    }
+}
+
+/// Joins two shifted segments that followed each other
+fn join(a: Segment, b: Segment) -> Vec<Segment> {
+    if norm(a.end() - b.start()) < 1e-9 {
+        return vec![a, b];
+    }
+    if let (Segment::Line { from: a0, to: a1 }, Segment::Line { from: b0, to: b1 }) = (a, b) {
+        let (u, v) = (a1 - a0, b1 - b0);
+        let det = u.x * v.y - u.y * v.x;
+        if det.abs() > 1e-12 {
+            let w = b0 - a0;
+            let k = (w.x * v.y - w.y * v.x) / det;
+            let corner = a0 + u * k;
+            return vec![
+                Segment::Line {
+                    from: a0,
+                    to: corner,
+                },
+                Segment::Line {
+                    from: corner,
+                    to: b1,
+                },
+            ];
+        }
+    }
+    vec![
+        a,
+        Segment::Line {
+            from: a.end(),
+            to: b.start(),
+        },
+        b,
+    ]
+}
+
+/// Defines a pair of curves on each side of a given curve, as `LinesLR` does for lines
+pub struct CurvesLR {
+    /// Curve on the left
+    pub l: CurvePath,
+    /// Curve on the right
+    pub r: CurvePath,
+}
+
+impl CurvesLR {
+    /// Creates a new pair of curves separated by a given width
+    pub fn new(c: &CurvePath, width: f64, tolerance: f64) -> Self {
+        CurvesLR {
+            l: c.shift_by(-width / 2., tolerance),
+            r: c.shift_by(width / 2., tolerance),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_valid, inner_corner_loop, make_drop, nothing};
+
+    fn distance_to_line(p: Coord, line: &LineString) -> f64 {
+        line.lines()
+            .map(|l| distance_to_segment(p, l.start, l.end))
+            .fold(f64::MAX, f64::min)
+    }
+
+    #[test]
+    fn flatten_within_tolerance() {
+        let circle = Segment::Arc {
+            center: coord! {x: 1., y: 1.},
+            radius: 2.,
+            start: 0.,
+            sweep: 2. * PI,
+        };
+        let coarse = circle.flatten(1e-2);
+        let fine = circle.flatten(1e-4);
+        assert!(fine.len() > 5 * coarse.len());
+        // The middles of the lines are within the tolerance of the circle
+        let mut previous = circle.start();
+        for p in coarse.iter() {
+            let middle = (previous + *p) / 2.;
+            assert!(2. - norm(middle - coord! {x: 1., y: 1.}) <= 1e-2);
+            previous = *p;
+        }
+        assert!(norm(*coarse.last().unwrap() - circle.start()) < 1e-12);
+        assert_eq!(1 << MAX_DEPTH, circle.flatten(0.).len());
+
+        let drop = make_drop();
+        let fine = drop.to_line_string(1e-5);
+        let coarse = drop.to_line_string(1e-3);
+        assert!(drop.is_closed());
+        assert!(coarse.0.len() < fine.0.len());
+        assert!(coarse.points().all(|p| distance_to_line(p.0, &fine) < 1e-3));
+        assert!(fine.points().all(|p| distance_to_line(p.0, &coarse) < 1e-3));
+        // Counterclockwise
+        assert!(!drop.is_hole());
+        assert_valid(&drop.to_polygon(1e-3), nothing);
+    }
+
+    #[test]
+    fn shift_drop() {
+        let drop = make_drop();
+        let width = 0.2;
+        let lr = CurvesLR::new(&drop, width, 1e-4);
+
+        assert!(lr.l.is_closed());
+        assert!(lr.r.is_closed());
+        // The arc is shifted by changing its radius
+        assert_eq!(
+            Segment::Arc {
+                center: coord! {x: 0., y: 0.},
+                radius: 1.1,
+                start: 0.,
+                sweep: PI
+            },
+            lr.r.segments[0]
+        );
+        // Away from the corners, the curves are half the width from the drop
+        let line = drop.to_line_string(1e-5);
+        for side in [&lr.l, &lr.r] {
+            let shifted = side.to_line_string(1e-5);
+            let far = shifted
+                .points()
+                .filter(|p| {
+                    norm(p.0 - coord! {x: 0., y: -2.}) > 0.5
+                        && norm(p.0 - coord! {x: 1., y: 0.}) > 0.5
+                })
+                .map(|p| (distance_to_line(p.0, &line) - width / 2.).abs())
+                .fold(0., f64::max);
+            assert!(far < 1e-3, "{}", far);
+        }
+        // Inside and outside the drop
+        let area = drop.to_polygon(1e-4).unsigned_area();
+        assert!(lr.l.to_polygon(1e-4).unsigned_area() < area);
+        assert!(lr.r.to_polygon(1e-4).unsigned_area() > area);
+        assert_valid(&lr.r.to_polygon(1e-4), nothing);
+        assert_valid(&lr.l.to_polygon(1e-4), inner_corner_loop);
+    }
+
+    #[test]
+    fn arc_as_cubics() {
+        let arc = Segment::Arc {
+            center: coord! {x: 0., y: 0.},
+            radius: 3.,
+            start: PI / 4.,
+            sweep: -3. * PI / 2.,
+        };
+        let cubics = arc.to_cubics();
+        assert_eq!(3, cubics.len());
+        assert!(norm(cubics[0].start() - arc.start()) < 1e-12);
+        assert!(norm(cubics[2].end() - arc.end()) < 1e-12);
+        for cubic in cubics {
+            for p in cubic.flatten(1e-5) {
+                assert!((norm(p) - 3.).abs() < 1e-3);
+            }
+        }
+        let data = make_drop().svg_path_data();
+        assert!(data.starts_with("M1 0A1 1 0 0 1 -1 "));
+        assert!(data.ends_with("C-1 -1 0 -1.5 0 -2L1 0Z"));
+    }
+}
@@ -0,0 +1,315 @@
+use geo_svg::{Style, ToSvgStr, ViewBox};
+use geo_types::{coord, Coord, LineString, Polygon};
+use std::fmt::Write;
+
+/// Which ring of a polygon a cut follows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ring {
+    Exterior,
+    /// Index of the hole in `Polygon::interiors`
+    Interior(usize),
+}
+
+/// One ring, or part of a ring, to cut
+#[derive(Clone, Debug)]
+pub struct Cut {
+    /// Index of the polygon the ring belongs to
+    pub polygon: usize,
+    pub ring: Ring,
+    /// The ring, starting and ending at the chosen start point, or an open part of it
+    pub line: LineString,
+    /// Whether the line is a whole ring
+    pub closed: bool,
+}
+
+/// The order in which the rings of some polygons are cut.
+///
+/// For each polygon, all the holes are cut before the exterior, so that the part
+/// stays in place until its last cut.  The holes are ordered to keep the travel of
+/// the head short: nearest neighbour first, improved by 2-opt.  Each ring starts at
+/// its vertex closest to where the previous cut ended.
+#[derive(Clone, Debug)]
+pub struct CutPath {
+    pub cuts: Vec<Cut>,
+}
+
+/// Maximum number of 2-opt passes over the holes of a polygon
+const MAX_PASSES: usize = 20;
+
+fn distance(a: Coord, b: Coord) -> f64 {
+    let d = a - b;
+    (d.x * d.x + d.y * d.y).sqrt()
+}
+
+/// Vertices of a ring, without the repeated closing one
+fn vertices(ring: &LineString) -> &[Coord] {
+    let coords = ring.0.as_slice();
+    if ring.is_closed() && coords.len() > 1 {
+        &coords[..coords.len() - 1]
+    } else {
+        coords
+    }
+}
+
+/// Index of the vertex of the ring closest to a position
+fn closest_vertex(ring: &LineString, from: Coord) -> usize {
+    vertices(ring)
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance(**a, from).total_cmp(&distance(**b, from)))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Rewrites a ring so it starts, and ends, at the given vertex
+fn start_at(ring: &LineString, start: usize) -> LineString {
+    let vs = vertices(ring);
+    if vs.is_empty() {
+        return ring.clone();
+    }
+    let mut coords: Vec<Coord> = vs[start..]
+        .iter()
+        .chain(vs[..start].iter())
+        .copied()
+        .collect();
+    coords.push(coords[0]);
+    LineString::new(coords)
+}
+
+impl CutPath {
+    /// Plans the cuts of a single polygon, starting from the origin
+    pub fn from_polygon(polygon: &Polygon) -> Self {
+        CutPath::plan(std::slice::from_ref(polygon), coord! {x: 0., y: 0.})
+    }
+
+    /// Plans the cuts of some polygons, with the head starting at `from`.
+    /// The polygons are themselves taken nearest first.
+    pub fn plan(polygons: &[Polygon], from: Coord) -> Self {
+        let mut cuts = Vec::new();
+        let mut position = from;
+        let mut remaining: Vec<usize> = (0..polygons.len()).collect();
+        while !remaining.is_empty() {
+            let k = (0..remaining.len())
+                .min_by(|a, b| {
+                    let da = CutPath::distance_to(&polygons[remaining[*a]], position);
+                    let db = CutPath::distance_to(&polygons[remaining[*b]], position);
+                    da.total_cmp(&db)
+                })
+                .unwrap();
+            let index = remaining.remove(k);
+            position = CutPath::plan_polygon(&polygons[index], index, position, &mut cuts);
+        }
+        CutPath { cuts }
+    }
+
+    /// Distance from a position to the closest vertex of a polygon
+    fn distance_to(polygon: &Polygon, from: Coord) -> f64 {
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .flat_map(|ring| vertices(ring).iter())
+            .map(|c| distance(*c, from))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Adds the cuts of one polygon, and returns where the head stops
+    fn plan_polygon(polygon: &Polygon, index: usize, from: Coord, cuts: &mut Vec<Cut>) -> Coord {
+        let holes = polygon.interiors();
+
+        // Nearest neighbour, from the closest vertex of each hole
+        let mut order: Vec<usize> = Vec::new();
+        let mut stops: Vec<Coord> = Vec::new();
+        let mut remaining: Vec<usize> = (0..holes.len()).collect();
+        let mut position = from;
+        while !remaining.is_empty() {
+            let (k, stop) = remaining
+                .iter()
+                .enumerate()
+                .map(|(k, h)| (k, holes[*h].0[closest_vertex(&holes[*h], position)]))
+                .min_by(|(_, a), (_, b)| distance(*a, position).total_cmp(&distance(*b, position)))
+                .unwrap();
+            order.push(remaining.remove(k));
+            stops.push(stop);
+            position = stop;
+        }
+
+        two_opt(from, &mut order, &mut stops);
+
+        // Each ring starts where the previous one ended
+        let mut position = from;
+        for h in order {
+            let start = closest_vertex(&holes[h], position);
+            let line = start_at(&holes[h], start);
+            position = line.0[0];
+            cuts.push(Cut {
+                polygon: index,
+                ring: Ring::Interior(h),
+                line,
+                closed: true,
+            });
+        }
+        let exterior = polygon.exterior();
+        let line = start_at(exterior, closest_vertex(exterior, position));
+        let position = line.0.first().copied().unwrap_or(position);
+        cuts.push(Cut {
+            polygon: index,
+            ring: Ring::Exterior,
+            line,
+            closed: true,
+        });
+        position
+    }
+
+    /// Length of the moves between the cuts, starting from `from`
+    pub fn travel(&self, from: Coord) -> f64 {
+        let mut position = from;
+        let mut travel = 0.;
+        for cut in self.cuts.iter() {
+            if let Some(start) = cut.line.0.first() {
+                travel += distance(position, *start);
+                position = *cut.line.0.last().unwrap();
+            }
+        }
+        travel
+    }
+
+    /// Cuts of one polygon, in order
+    pub fn of_polygon(&self, index: usize) -> impl Iterator<Item = &Cut> {
+        self.cuts.iter().filter(move |cut| cut.polygon == index)
+    }
+}
+
+/// Improves an open tour from `from` through the stops, by reversing sections
+/// of it as long as this shortens it
+fn two_opt(from: Coord, order: &mut [usize], stops: &mut [Coord]) {
+    let n = stops.len();
+    let at = |stops: &[Coord], i: usize| if i == 0 { from } else { stops[i - 1] };
+    for _ in 0..MAX_PASSES {
+        let mut improved = false;
+        // Point i of the tour is `from` for 0, then stops[i - 1]
+        for i in 0..n {
+            for j in (i + 2)..=n {
+                let (a, b, c) = (at(stops, i), at(stops, i + 1), at(stops, j));
+                // The last section of the tour has no point after it
+                let (before, after) = match (j < n).then(|| at(stops, j + 1)) {
+                    Some(d) => (
+                        distance(a, b) + distance(c, d),
+                        distance(a, c) + distance(b, d),
+                    ),
+                    None => (distance(a, b), distance(a, c)),
+                };
+                if after + 1e-12 < before {
+                    stops[i..j].reverse();
+                    order[i..j].reverse();
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+}
+
+/// A cut path is written as a single svg path, its sub-paths in cut order.
+/// Only whole rings are closed.
+impl ToSvgStr for CutPath {
+    fn to_svg_str(&self, style: &Style) -> String {
+        let mut path = String::new();
+        for cut in self.cuts.iter() {
+            let mut coords = cut.line.coords();
+            if let Some(first) = coords.next() {
+                write!(path, "M {:?} {:?}", first.x, first.y).unwrap();
+            }
+            for c in coords {
+                write!(path, " L {:?} {:?}", c.x, c.y).unwrap();
+            }
+            if cut.closed {
+                write!(path, " Z").unwrap();
+            }
+            write!(path, " ").unwrap();
+        }
+        format!(r#"<path fill-rule="evenodd" d="{path}"{style}/>"#)
+    }
+
+    fn viewbox(&self, style: &Style) -> ViewBox {
+        self.cuts.iter().fold(ViewBox::default(), |viewbox, cut| {
+            viewbox.add(&cut.line.viewbox(style))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::{assert_valid, holes_either_way, make_base, make_hexagon_config};
+    use geo_types::polygon;
+
+    #[test]
+    fn holes_before_exterior() {
+        // Given a hexagon, with its holes in construction order
+        let hexa = Hexagon::new_at_base(&make_base(), &make_hexagon_config());
+        let origin = coord! {x: -4., y: -4.};
+        assert_valid(&hexa.polygon, holes_either_way);
+
+        // When
+        let path = CutPath::plan(std::slice::from_ref(&hexa.polygon), origin);
+
+        // Then every hole is cut once, before the exterior
+        let holes = hexa.polygon.interiors().len();
+        assert_eq!(holes + 1, path.cuts.len());
+        assert_eq!(Ring::Exterior, path.cuts.last().unwrap().ring);
+        let mut seen: Vec<usize> = path
+            .cuts
+            .iter()
+            .filter_map(|c| match c.ring {
+                Ring::Interior(h) => Some(h),
+                Ring::Exterior => None,
+            })
+            .collect();
+        seen.sort();
+        assert_eq!((0..holes).collect::<Vec<usize>>(), seen);
+
+        // and the travel is shorter than in construction order
+        let naive = CutPath {
+            cuts: hexa
+                .polygon
+                .interiors()
+                .iter()
+                .enumerate()
+                .map(|(h, line)| Cut {
+                    polygon: 0,
+                    ring: Ring::Interior(h),
+                    line: line.clone(),
+                    closed: true,
+                })
+                .collect(),
+        };
+        assert!(path.travel(origin) < naive.travel(origin));
+    }
+
+    #[test]
+    fn start_points() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.), (x: 0., y: 10.)],
+            interiors: [
+                [(x: 6., y: 6.), (x: 8., y: 6.), (x: 8., y: 8.), (x: 6., y: 8.)],
+                [(x: 2., y: 2.), (x: 4., y: 2.), (x: 4., y: 4.), (x: 2., y: 4.)],
+            ],
+        );
+
+        assert_valid(&square, holes_either_way);
+        let path = CutPath::from_polygon(&square);
+
+        let starts: Vec<Coord> = path.cuts.iter().map(|c| c.line.0[0]).collect();
+        assert_eq!(
+            vec![
+                coord! {x: 2., y: 2.},
+                coord! {x: 6., y: 6.},
+                coord! {x: 10., y: 10.}
+            ],
+            starts
+        );
+        assert!(path.cuts.iter().all(|c| c.line.is_closed()));
+    }
+}
@@ -0,0 +1,575 @@
+use geo_types::{coord, Coord, LineString, MultiLineString, Polygon};
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::fs;
+
+use crate::curves::{CurvePath, Segment};
+use crate::cutpath::{CutPath, Ring};
+
+/// Layer of the exteriors of the polygons
+pub const FRAME_LAYER: &str = "FRAME";
+/// Layer of the holes
+pub const HOLES_LAYER: &str = "HOLES";
+/// Layer of the construction lines, not meant to be cut
+pub const CONSTRUCTION_LAYER: &str = "CONSTRUCTION";
+
+/// Handle of the block record of the model space, owner of the entities
+const MODEL_SPACE: u32 = 1;
+
+/// Units of the coordinates, recorded in the header of the file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DxfUnits {
+    Inches,
+    Millimeters,
+    Centimeters,
+}
+
+impl DxfUnits {
+    /// Value of `$INSUNITS`
+    fn code(&self) -> u8 {
+        match self {
+            DxfUnits::Inches => 1,
+            DxfUnits::Millimeters => 4,
+            DxfUnits::Centimeters => 5,
+        }
+    }
+
+    /// Value of `$MEASUREMENT`: 0 for imperial, 1 for metric
+    fn measurement(&self) -> u8 {
+        match self {
+            DxfUnits::Inches => 0,
+            _ => 1,
+        }
+    }
+}
+
+/// A DXF file being written: its entities, and the next free handle.
+///
+/// The entities come first; the header, whose `$HANDSEED` must be above every
+/// handle, and the tables are written around them by `finish`.
+struct Dxf {
+    entities: String,
+    next: u32,
+}
+
+impl Dxf {
+    fn new() -> Self {
+        Dxf {
+            entities: String::new(),
+            next: MODEL_SPACE + 1,
+        }
+    }
+
+    /// Takes a new handle
+    fn handle(&mut self) -> u32 {
+        self.next += 1;
+        self.next - 1
+    }
+
+    /// Starts an entity of the model space, up to its layer
+    fn entity(&mut self, kind: &str, layer: &str) {
+        let handle = self.handle();
+        let dxf = &mut self.entities;
+        group(dxf, 0, kind);
+        group(dxf, 5, format!("{:X}", handle));
+        group(dxf, 330, format!("{:X}", MODEL_SPACE));
+        group(dxf, 100, "AcDbEntity");
+        group(dxf, 8, layer);
+    }
+
+    /// Writes the whole file: header, tables, blocks, entities and objects
+    fn finish(mut self, units: DxfUnits) -> String {
+        let mut tables = String::new();
+        let paper_space = self.tables(&mut tables);
+        let mut blocks = String::new();
+        self.blocks(&mut blocks, paper_space);
+        let mut objects = String::new();
+        self.objects(&mut objects);
+
+        let mut dxf = String::new();
+        group(&mut dxf, 0, "SECTION");
+        group(&mut dxf, 2, "HEADER");
+        group(&mut dxf, 9, "$ACADVER");
+        group(&mut dxf, 1, "AC1015");
+        group(&mut dxf, 9, "$HANDSEED");
+        group(&mut dxf, 5, format!("{:X}", self.next));
+        group(&mut dxf, 9, "$INSUNITS");
+        group(&mut dxf, 70, units.code());
+        group(&mut dxf, 9, "$MEASUREMENT");
+        group(&mut dxf, 70, units.measurement());
+        group(&mut dxf, 0, "ENDSEC");
+        group(&mut dxf, 0, "SECTION");
+        group(&mut dxf, 2, "CLASSES");
+        group(&mut dxf, 0, "ENDSEC");
+        dxf.push_str(&tables);
+        dxf.push_str(&blocks);
+        group(&mut dxf, 0, "SECTION");
+        group(&mut dxf, 2, "ENTITIES");
+        dxf.push_str(&self.entities);
+        group(&mut dxf, 0, "ENDSEC");
+        dxf.push_str(&objects);
+        group(&mut dxf, 0, "EOF");
+        dxf
+    }
+
+    /// Starts a table of `count` records, and returns its handle
+    fn table(&mut self, dxf: &mut String, name: &str, count: usize) -> u32 {
+        let handle = self.handle();
+        group(dxf, 0, "TABLE");
+        group(dxf, 2, name);
+        group(dxf, 5, format!("{:X}", handle));
+        group(dxf, 330, 0);
+        group(dxf, 100, "AcDbSymbolTable");
+        group(dxf, 70, count);
+        handle
+    }
+
+    /// Starts a record of a table, up to its name
+    fn record(&mut self, dxf: &mut String, kind: &str, table: u32, subclass: &str, name: &str) {
+        let handle = self.handle();
+        group(dxf, 0, kind);
+        // The dimension styles have their own code for the handle
+        group(
+            dxf,
+            if kind == "DIMSTYLE" { 105 } else { 5 },
+            format!("{:X}", handle),
+        );
+        group(dxf, 330, format!("{:X}", table));
+        group(dxf, 100, "AcDbSymbolTableRecord");
+        group(dxf, 100, subclass);
+        group(dxf, 2, name);
+        group(dxf, 70, 0);
+    }
+
+    /// Writes the tables that an R2000 file needs, with the layers, and
+    /// returns the handle of the block record of the paper space
+    fn tables(&mut self, dxf: &mut String) -> u32 {
+        group(dxf, 0, "SECTION");
+        group(dxf, 2, "TABLES");
+
+        self.table(dxf, "VPORT", 0);
+        group(dxf, 0, "ENDTAB");
+
+        let ltypes = self.table(dxf, "LTYPE", 3);
+        for (name, description) in [
+            ("ByBlock", ""),
+            ("ByLayer", ""),
+            ("Continuous", "Solid line"),
+        ] {
+            self.record(dxf, "LTYPE", ltypes, "AcDbLinetypeTableRecord", name);
+            group(dxf, 3, description);
+            group(dxf, 72, 65);
+            group(dxf, 73, 0);
+            group(dxf, 40, 0.0);
+        }
+        group(dxf, 0, "ENDTAB");
+
+        let layers = self.table(dxf, "LAYER", 4);
+        for (name, color) in [
+            ("0", 7),
+            (FRAME_LAYER, 7),
+            (HOLES_LAYER, 1),
+            (CONSTRUCTION_LAYER, 8),
+        ] {
+            self.record(dxf, "LAYER", layers, "AcDbLayerTableRecord", name);
+            group(dxf, 62, color);
+            group(dxf, 6, "Continuous");
+        }
+        group(dxf, 0, "ENDTAB");
+
+        let styles = self.table(dxf, "STYLE", 1);
+        self.record(dxf, "STYLE", styles, "AcDbTextStyleTableRecord", "Standard");
+        group(dxf, 40, 0.0);
+        group(dxf, 41, 1.0);
+        group(dxf, 50, 0.0);
+        group(dxf, 71, 0);
+        group(dxf, 42, 2.5);
+        group(dxf, 3, "txt");
+        group(dxf, 4, "");
+        group(dxf, 0, "ENDTAB");
+
+        self.table(dxf, "VIEW", 0);
+        group(dxf, 0, "ENDTAB");
+        self.table(dxf, "UCS", 0);
+        group(dxf, 0, "ENDTAB");
+
+        let appids = self.table(dxf, "APPID", 1);
+        self.record(dxf, "APPID", appids, "AcDbRegAppTableRecord", "ACAD");
+        group(dxf, 0, "ENDTAB");
+
+        let dimstyles = self.table(dxf, "DIMSTYLE", 1);
+        group(dxf, 100, "AcDbDimStyleTable");
+        group(dxf, 71, 0);
+        self.record(
+            dxf,
+            "DIMSTYLE",
+            dimstyles,
+            "AcDbDimStyleTableRecord",
+            "Standard",
+        );
+        group(dxf, 0, "ENDTAB");
+
+        let records = self.table(dxf, "BLOCK_RECORD", 2);
+        let paper_space = self.handle();
+        for (handle, name) in [(MODEL_SPACE, "*Model_Space"), (paper_space, "*Paper_Space")] {
+            group(dxf, 0, "BLOCK_RECORD");
+            group(dxf, 5, format!("{:X}", handle));
+            group(dxf, 330, format!("{:X}", records));
+            group(dxf, 100, "AcDbSymbolTableRecord");
+            group(dxf, 100, "AcDbBlockTableRecord");
+            group(dxf, 2, name);
+        }
+        group(dxf, 0, "ENDTAB");
+
+        group(dxf, 0, "ENDSEC");
+        paper_space
+    }
+
+    /// Writes the empty blocks of the model and paper spaces
+    fn blocks(&mut self, dxf: &mut String, paper_space: u32) {
+        group(dxf, 0, "SECTION");
+        group(dxf, 2, "BLOCKS");
+        for (record, name) in [(MODEL_SPACE, "*Model_Space"), (paper_space, "*Paper_Space")] {
+            let handle = self.handle();
+            group(dxf, 0, "BLOCK");
+            group(dxf, 5, format!("{:X}", handle));
+            group(dxf, 330, format!("{:X}", record));
+            group(dxf, 100, "AcDbEntity");
+            if record == paper_space {
+                group(dxf, 67, 1);
+            }
+            group(dxf, 8, "0");
+            group(dxf, 100, "AcDbBlockBegin");
+            group(dxf, 2, name);
+            group(dxf, 70, 0);
+            group(dxf, 10, 0.0);
+            group(dxf, 20, 0.0);
+            group(dxf, 30, 0.0);
+            group(dxf, 3, name);
+            group(dxf, 1, "");
+            let handle = self.handle();
+            group(dxf, 0, "ENDBLK");
+            group(dxf, 5, format!("{:X}", handle));
+            group(dxf, 330, format!("{:X}", record));
+            group(dxf, 100, "AcDbEntity");
+            if record == paper_space {
+                group(dxf, 67, 1);
+            }
+            group(dxf, 8, "0");
+            group(dxf, 100, "AcDbBlockEnd");
+        }
+        group(dxf, 0, "ENDSEC");
+    }
+
+    /// Writes the root dictionary, holding the empty dictionary of the groups
+    fn objects(&mut self, dxf: &mut String) {
+        let root = self.handle();
+        let groups = self.handle();
+        group(dxf, 0, "SECTION");
+        group(dxf, 2, "OBJECTS");
+        group(dxf, 0, "DICTIONARY");
+        group(dxf, 5, format!("{:X}", root));
+        group(dxf, 330, 0);
+        group(dxf, 100, "AcDbDictionary");
+        group(dxf, 281, 1);
+        group(dxf, 3, "ACAD_GROUP");
+        group(dxf, 350, format!("{:X}", groups));
+        group(dxf, 0, "DICTIONARY");
+        group(dxf, 5, format!("{:X}", groups));
+        group(dxf, 330, format!("{:X}", root));
+        group(dxf, 100, "AcDbDictionary");
+        group(dxf, 281, 1);
+        group(dxf, 0, "ENDSEC");
+    }
+}
+
+/// Writes a group: its code, right aligned on 3 characters, then its value
+fn group(dxf: &mut String, code: u16, value: impl std::fmt::Display) {
+    write!(dxf, "{:>3}\n{}\n", code, value).unwrap();
+}
+
+fn lwpolyline(dxf: &mut Dxf, line: &LineString, layer: &str, closed: bool) {
+    let vertices: Vec<(Coord, f64)> = line.0.iter().map(|c| (*c, 0.)).collect();
+    bulged_lwpolyline(dxf, &vertices, layer, closed && line.is_closed());
+}
+
+/// Writes a polyline whose vertices may start an arc.  The bulge of a vertex
+/// is the tangent of a quarter of the angle of the arc, positive when it turns
+/// counterclockwise; 0 for a line.
+fn bulged_lwpolyline(dxf: &mut Dxf, vertices: &[(Coord, f64)], layer: &str, closed: bool) {
+    let mut vertices = vertices;
+    // The closing vertex is given by the flag
+    if closed && vertices.len() > 1 {
+        vertices = &vertices[..vertices.len() - 1];
+    }
+    dxf.entity("LWPOLYLINE", layer);
+    let dxf = &mut dxf.entities;
+    group(dxf, 100, "AcDbPolyline");
+    group(dxf, 90, vertices.len());
+    group(dxf, 70, if closed { 1 } else { 0 });
+    for (c, bulge) in vertices {
+        group(dxf, 10, c.x);
+        group(dxf, 20, c.y);
+        if *bulge != 0. {
+            group(dxf, 42, bulge);
+        }
+    }
+}
+
+/// Writes a bezier as a spline of degree 3, with its four control points
+fn spline(dxf: &mut Dxf, points: [Coord; 4], layer: &str) {
+    dxf.entity("SPLINE", layer);
+    let dxf = &mut dxf.entities;
+    group(dxf, 100, "AcDbSpline");
+    // Planar, with its normal
+    group(dxf, 210, 0.0);
+    group(dxf, 220, 0.0);
+    group(dxf, 230, 1.0);
+    group(dxf, 70, 8);
+    group(dxf, 71, 3);
+    group(dxf, 72, 8);
+    group(dxf, 73, 4);
+    group(dxf, 74, 0);
+    for knot in [0, 0, 0, 0, 1, 1, 1, 1] {
+        group(dxf, 40, knot);
+    }
+    for c in points {
+        group(dxf, 10, c.x);
+        group(dxf, 20, c.y);
+        group(dxf, 30, 0);
+    }
+}
+
+/// Writes a curve path: its runs of lines and arcs as polylines, and its
+/// beziers as splines
+fn curve_path(dxf: &mut Dxf, path: &CurvePath, layer: &str) {
+    let mut run: Vec<(Coord, f64)> = vec![];
+    let flush = |dxf: &mut Dxf, run: &mut Vec<(Coord, f64)>, end: Coord, closed: bool| {
+        if !run.is_empty() {
+            run.push((end, 0.));
+            bulged_lwpolyline(dxf, run, layer, closed);
+            run.clear();
+        }
+    };
+    for segment in path.segments.iter() {
+        match *segment {
+            Segment::Line { from, .. } => run.push((from, 0.)),
+            Segment::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                // Halves of a large arc, to keep the bulges small
+                let n = if sweep.abs() > PI { 2 } else { 1 };
+                for i in 0..n {
+                    let half = Segment::Arc {
+                        center,
+                        radius,
+                        start: start + sweep * i as f64 / n as f64,
+                        sweep: sweep / n as f64,
+                    };
+                    run.push((half.start(), (sweep / n as f64 / 4.).tan()));
+                }
+            }
+            Segment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                flush(dxf, &mut run, from, false);
+                spline(dxf, [from, ctrl1, ctrl2, to], layer);
+            }
+        }
+    }
+    // A path without beziers is a single polyline, closed by its flag
+    let closed = path.is_closed()
+        && path
+            .segments
+            .iter()
+            .all(|s| !matches!(s, Segment::Cubic { .. }));
+    if let Some(last) = path.segments.last() {
+        flush(dxf, &mut run, last.end(), closed);
+    }
+}
+
+/// Writes a cut path, and construction lines, as an ASCII DXF file.
+///
+/// The file is an R2000 one, whose header records the units.  Every ring is
+/// a `LWPOLYLINE`, on the frame layer (white) or the holes layer (red), in cut
+/// order; the construction lines are open polylines on their own layer (grey).
+pub fn cut_path_to_dxf(path: &CutPath, construction: &MultiLineString, units: DxfUnits) -> String {
+    let mut dxf = Dxf::new();
+    for cut in path.cuts.iter() {
+        let layer = match cut.ring {
+            Ring::Exterior => FRAME_LAYER,
+            Ring::Interior(_) => HOLES_LAYER,
+        };
+        lwpolyline(&mut dxf, &cut.line, layer, cut.closed);
+    }
+    for line in construction.iter() {
+        lwpolyline(&mut dxf, line, CONSTRUCTION_LAYER, false);
+    }
+    dxf.finish(units)
+}
+
+/// Writes curve paths as an ASCII DXF file, keeping their curves.
+///
+/// The closed paths turning clockwise are on the holes layer, the others on
+/// the frame layer.  Lines and arcs are written as `LWPOLYLINE`s, an arc
+/// being a vertex with a bulge; beziers are written as `SPLINE`s.
+pub fn curves_to_dxf(paths: &[CurvePath], units: DxfUnits) -> String {
+    let mut dxf = Dxf::new();
+    for path in paths {
+        let layer = if path.is_hole() {
+            HOLES_LAYER
+        } else {
+            FRAME_LAYER
+        };
+        curve_path(&mut dxf, path, layer);
+    }
+    dxf.finish(units)
+}
+
+/// Writes polygons, and construction lines, as an ASCII DXF file
+pub fn polygons_to_dxf(
+    polygons: &[Polygon],
+    construction: &MultiLineString,
+    units: DxfUnits,
+) -> String {
+    let path = CutPath::plan(polygons, coord! {x: 0., y: 0.});
+    cut_path_to_dxf(&path, construction, units)
+}
+
+pub fn save_curves_as_dxf(paths: &[CurvePath], units: DxfUnits, filename: &str) {
+    fs::write(filename, curves_to_dxf(paths, units)).expect("Unable to write file");
+}
+
+pub fn save_polygons_as_dxf(
+    polygons: &[Polygon],
+    construction: &MultiLineString,
+    units: DxfUnits,
+    filename: &str,
+) {
+    fs::write(filename, polygons_to_dxf(polygons, construction, units))
+        .expect("Unable to write file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kumiko::KumikoFigure;
+    use crate::test_support::{
+        assert_valid, holes_either_way, make_drop, make_triskell_base, make_triskell_config,
+    };
+    use crate::triskell::Triskell;
+    use geo_types::{line_string, polygon};
+
+    #[test]
+    fn square_with_hole() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.), (x: 0., y: 4.)],
+            interiors: [[(x: 1., y: 1.), (x: 2., y: 1.), (x: 2., y: 2.)]],
+        );
+        let diagonal = MultiLineString::new(vec![line_string![(x: 0., y: 0.), (x: 4., y: 4.)]]);
+
+        assert_valid(&square, holes_either_way);
+        let dxf = polygons_to_dxf(&[square], &diagonal, DxfUnits::Millimeters);
+
+        assert!(dxf.contains("  9\n$ACADVER\n  1\nAC1015\n"));
+        assert!(dxf.contains("  9\n$INSUNITS\n 70\n4\n"));
+        assert_eq!(3, dxf.matches("LWPOLYLINE").count());
+        // The hole, then the exterior, then the construction line
+        let layers: Vec<&str> = dxf
+            .split("LWPOLYLINE\n")
+            .skip(1)
+            .map(|s| s.split("AcDbEntity\n  8\n").nth(1).unwrap())
+            .map(|s| s.lines().next().unwrap())
+            .collect();
+        assert_eq!(vec![HOLES_LAYER, FRAME_LAYER, CONSTRUCTION_LAYER], layers);
+        assert!(dxf.contains(
+            "FRAME\n100\nAcDbPolyline\n 90\n4\n 70\n1\n 10\n0\n 20\n0\n 10\n4\n 20\n0\n"
+        ));
+        assert!(dxf.contains("CONSTRUCTION\n100\nAcDbPolyline\n 90\n2\n 70\n0\n"));
+        assert!(dxf.ends_with("  0\nEOF\n"));
+    }
+
+    #[test]
+    fn handles_below_seed() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.), (x: 0., y: 4.)],
+            interiors: [[(x: 1., y: 1.), (x: 2., y: 1.), (x: 2., y: 2.)]],
+        );
+
+        let dxf = polygons_to_dxf(&[square], &MultiLineString::new(vec![]), DxfUnits::Inches);
+
+        let lines: Vec<&str> = dxf.lines().collect();
+        let seed = lines.iter().position(|l| *l == "$HANDSEED").unwrap();
+        let (seed, rest) = (lines[seed + 2], &lines[seed + 3..]);
+        let seed = u32::from_str_radix(seed, 16).unwrap();
+        let mut handles: Vec<u32> = rest
+            .chunks(2)
+            .filter(|g| g[0] == "  5" || g[0] == "105")
+            .map(|g| u32::from_str_radix(g[1], 16).unwrap())
+            .collect();
+        let count = handles.len();
+        handles.sort();
+        handles.dedup();
+        // Unique, and below the seed
+        assert_eq!(count, handles.len());
+        assert!(handles.iter().all(|h| *h < seed));
+        assert!(dxf.ends_with("  0\nEOF\n"));
+    }
+
+    #[test]
+    fn triskell_with_rays() {
+        let base = make_triskell_base();
+        let triskell = Triskell::new_at_base(&base, &make_triskell_config());
+        assert_valid(triskell.polygon(), holes_either_way);
+
+        let dxf = polygons_to_dxf(
+            std::slice::from_ref(triskell.polygon()),
+            &triskell.rays,
+            DxfUnits::Centimeters,
+        );
+
+        let rings = 1 + triskell.polygon().interiors().len();
+        assert_eq!(
+            rings + triskell.rays.0.len(),
+            dxf.matches("LWPOLYLINE").count()
+        );
+        save_polygons_as_dxf(
+            std::slice::from_ref(triskell.polygon()),
+            &triskell.rays,
+            DxfUnits::Centimeters,
+            "test_figures/triskell.dxf",
+        );
+    }
+
+    #[test]
+    fn curves_kept() {
+        let drop = make_drop();
+        let circle = CurvePath::new(vec![Segment::Arc {
+            center: coord! {x: 0., y: -0.5},
+            radius: 0.3,
+            start: 0.,
+            sweep: -2. * PI,
+        }]);
+
+        let dxf = curves_to_dxf(&[drop.clone(), circle], DxfUnits::Centimeters);
+
+        // The half circle, then the bezier, then the closing line; then the circle
+        assert_eq!(3, dxf.matches("LWPOLYLINE").count());
+        assert_eq!(1, dxf.matches("SPLINE").count());
+        assert!(dxf.contains("FRAME\n100\nAcDbSpline\n"));
+        assert!(
+            dxf.contains("FRAME\n100\nAcDbPolyline\n 90\n2\n 70\n0\n 10\n1\n 20\n0\n 42\n0.99999")
+        );
+        assert!(dxf.contains(" 10\n0\n 20\n-1.5\n 30\n0\n"));
+        // The clockwise circle is a hole, in two halves
+        assert!(dxf.contains("HOLES\n100\nAcDbPolyline\n 90\n2\n 70\n1\n"));
+        assert_eq!(2, dxf.matches(" 42\n-0.99999").count());
+        save_curves_as_dxf(&[drop], DxfUnits::Centimeters, "test_figures/drop.dxf");
+    }
+}
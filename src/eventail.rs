@@ -3,11 +3,12 @@ use crate::oglines::{angle_between, triangle, InfiniteLine, LinesLR, Rotation};
 use geo_types::{LineString, MultiLineString, Point, Polygon};
 
 /// An eventail (fan)
-pub struct Eventail<'b> {
+#[derive(Clone)]
+pub struct Eventail {
     polygon: Polygon,
     pub rays: MultiLineString,
     points: Vec<Point>,
-    base: &'b Base,
+    base: Base,
 }
 
 pub struct EventailConfig {
@@ -28,7 +29,7 @@ impl KumikoConfigTrait for EventailConfig {
 }
 
 /// A rhombo-like eventail (fan)
-impl<'b> KumikoFigure<EventailConfig> for Eventail<'b> {
+impl KumikoFigure<EventailConfig> for Eventail {
     fn polygon(&self) -> &Polygon {
         &self.polygon
     }
@@ -37,10 +38,10 @@ impl<'b> KumikoFigure<EventailConfig> for Eventail<'b> {
     }
 }
 
-impl<'b> Eventail<'b> {
+impl Eventail {
     /// The points must form a rhomboid
     ///
-    pub fn new_at_base(base: &'b Base, config: &dyn EventailConfigTrait) -> Self {
+    pub fn new_at_base(base: &Base, config: &dyn EventailConfigTrait) -> Self {
         let pu = base.origin + base.u;
         let pv = base.origin + base.v;
         let puv = pu + base.v;
@@ -71,7 +72,7 @@ impl<'b> Eventail<'b> {
             polygon: Polygon::new(LineString(pts.iter().map(|p| p.0).collect()), holes),
             points: vec![base.origin, pu, puv, pv],
             rays,
-            base,
+            base: base.clone(),
         }
     }
     /// Creates all the lines
@@ -141,8 +142,10 @@ mod tests {
     use super::*;
     use crate::oglines::points2geometry;
     use crate::svg::write_svg;
+    use crate::test_support::{assert_valid, holes_either_way};
+    use geo::Translate;
     use geo_svg::{Color, ToSvg};
-    use geo_types::point;
+    use geo_types::{point, Coord};
 
     fn make_points() -> Vec<Point> {
         let side: f64 = 4.0;
@@ -184,6 +187,7 @@ mod tests {
             .with_stroke_width(0.01)
             .with_stroke_color(Color::Rgb(100, 0, 200))
             .with_fill_opacity(0.2);
+        assert_valid(eventail.polygon(), holes_either_way);
         let svg_eventail = eventail.draw_figure();
         let svg_base = eventail.base.draw();
 
@@ -195,4 +199,48 @@ mod tests {
 
         write_svg(&svg, "test_figures/eventail.svg");
     }
+
+    /// An eventail on a base moved by `dx`, that does not outlive this function
+    fn make_eventail(dx: f64) -> Eventail {
+        let config = EventailConfig {
+            parent: KumikoConfig::default(),
+        };
+        let mut points = make_points();
+        // u and v are vectors: only the origin moves
+        points[0] += point! {x: dx, y: 0.};
+        let base = make_base(&points);
+        Eventail::new_at_base(&base, &config)
+    }
+
+    #[test]
+    fn eventails_in_threads() {
+        let handles: Vec<_> = (0..4)
+            .map(|k| std::thread::spawn(move || make_eventail(10. * k as f64)))
+            .collect();
+        let eventails: Vec<Eventail> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        let copy = eventails[3].clone();
+        assert_eq!(eventails[3].polygon(), copy.polygon());
+        assert_eq!(copy.base.origin, point! {x: 30., y: 0.});
+        assert!(eventails
+            .iter()
+            .enumerate()
+            .all(|(k, e)| e.base.origin == point! {x: 10. * k as f64, y: 0.}));
+        // The same figure, moved
+        let moved = eventails[0].polygon().translate(30., 0.);
+        let rings = |p: &Polygon| -> Vec<Coord> {
+            p.exterior()
+                .coords()
+                .chain(p.interiors().iter().flat_map(|h| h.coords()))
+                .copied()
+                .collect()
+        };
+        let (expected, got) = (rings(&moved), rings(copy.polygon()));
+        assert_eq!(expected.len(), got.len());
+        assert!(expected
+            .iter()
+            .zip(got.iter())
+            .all(|(a, b)| (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9));
+        assert_valid(copy.polygon(), holes_either_way);
+    }
 }
@@ -1,6 +1,7 @@
 use geo::{coord, line_string, Coord, LineString, Point, Polygon};
 
-use crate::operations::intersect;
+use crate::operations::{intersect, intersect_with, BoundaryPolicy};
+use crate::precision::GeometryContext;
 
 pub trait Frame {
     fn frame(&self, interiors: &[LineString]) -> Polygon {
@@ -9,6 +10,14 @@ pub trait Frame {
 
         intersect(interiors, contour_line, clipping_line)
     }
+    /// Frames the interiors, those cut by the inner contour following the policy
+    fn frame_with(&self, interiors: &[LineString], policy: BoundaryPolicy) -> Polygon {
+        let contour_line = self.outer_contour();
+        let clipping_line = self.inner_contour();
+        let context = GeometryContext::for_geometry(&contour_line);
+
+        intersect_with(interiors, contour_line, clipping_line, &context, policy)
+    }
     /// Returns the four inner vertices of the frame
     fn inner_contour(&self) -> LineString;
     fn outer_contour(&self) -> LineString;
@@ -332,4 +341,63 @@ mod tests {
         ];
         assert_eq!(expected_top_coords, top_cs);
     }
+
+    #[test]
+    fn boundary_policies() {
+        use super::{Frame, SimpleFrame};
+        use crate::operations::BoundaryPolicy;
+        use crate::svg::save_polygon_as_svg;
+        use crate::test_support::{assert_valid, nothing};
+        use geo::{BoundingRect, LineString};
+
+        // Given a hole inside, a hole cut in two, a sliver and a hole nearly
+        // touching the frame
+        //
+        //  +--------------+
+        //  |   N          |
+        //  |          S  -+-
+        //  |   A     B |  |
+        //  +-----------+--+
+        let square = |x0: f64, y0: f64, x1: f64, y1: f64| {
+            LineString::from(vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)])
+        };
+        let near = square(2., 7., 4., 9.95);
+        let interiors = vec![
+            square(2., 2., 4., 4.),
+            square(8., 2., 12., 4.),
+            square(9.95, 6., 11., 7.),
+            near.clone(),
+        ];
+        let frame = SimpleFrame::new(point! {x: 0., y: 0.}, 10., 10., 1.);
+
+        // When, Then
+        let kept = frame.frame_with(&interiors, BoundaryPolicy::Keep);
+        assert_eq!(4, kept.interiors().len());
+        // Kept as it is, turned clockwise as a hole
+        let reversed = |ring: &LineString| -> LineString { ring.0.iter().rev().copied().collect() };
+        assert_eq!(reversed(&near), kept.interiors()[3]);
+
+        let thin = BoundaryPolicy::DropBelow {
+            area: 0.2,
+            width: 0.2,
+        };
+        let dropped = frame.frame_with(&interiors, thin);
+        assert_eq!(3, dropped.interiors().len());
+
+        let whole = frame.frame_with(&interiors, BoundaryPolicy::DropPartial);
+        assert_eq!(
+            vec![reversed(&interiors[0]), reversed(&near)],
+            whole.interiors()
+        );
+
+        // The sliver is gone, and the hole near the frame now reaches it
+        let snapped = frame.frame_with(&interiors, BoundaryPolicy::Snap(0.1));
+        assert_eq!(3, snapped.interiors().len());
+        let top = snapped.interiors()[2].bounding_rect().unwrap().max().y;
+        assert!((top - 10.).abs() < 1e-9);
+        save_polygon_as_svg(&snapped, "test_figures/frame_snapped.svg");
+        for figure in [kept, dropped, whole, snapped] {
+            assert_valid(&figure, nothing);
+        }
+    }
 }
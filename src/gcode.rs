@@ -0,0 +1,379 @@
+use geo::orient::{Direction, Orient};
+use geo_clipper::{Clipper, EndType};
+use geo_types::{coord, Coord, Polygon};
+use std::fmt::{self, Write};
+use std::fs;
+
+use crate::cutpath::{Cut, CutPath};
+use crate::precision::GeometryContext;
+
+/// The machine the G-code is written for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Machine {
+    /// A router: the spindle is started at the given speed, and each ring is cut
+    /// down to the thickness in passes, retracting to the safe height between rings
+    Router {
+        spindle_speed: f64,
+        safe_height: f64,
+    },
+    /// A GRBL laser, in laser mode (`$32=1`): the power, from 0 to the `$30` maximum,
+    /// is constant with `M3`, or follows the speed with `M4` when dynamic.
+    /// The Z axis is not moved, each pass goes over the rings again.
+    Laser { power: u32, dynamic: bool },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GcodeError {
+    /// The depth of a pass is not a positive number
+    DepthPerPass(f64),
+    /// The thickness of the material is not a positive number
+    Thickness(f64),
+}
+
+impl fmt::Display for GcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GcodeError::DepthPerPass(depth) => {
+                write!(f, "depth per pass {} is not a positive number", depth)
+            }
+            GcodeError::Thickness(thickness) => {
+                write!(f, "thickness {} is not a positive number", thickness)
+            }
+        }
+    }
+}
+
+pub struct GcodeConfig {
+    /// Speed of the cutting moves, in mm/min
+    pub feed_rate: f64,
+    /// Speed of the plunges into the material, in mm/min
+    pub plunge_rate: f64,
+    /// Depth cut by each pass
+    pub depth_per_pass: f64,
+    /// Thickness of the material
+    pub thickness: f64,
+    /// Width of the cut (tool or kerf): the path of the tool is kept at half of it
+    /// from the material
+    pub tool_diameter: f64,
+    pub machine: Machine,
+}
+
+impl Default for GcodeConfig {
+    fn default() -> GcodeConfig {
+        GcodeConfig {
+            feed_rate: 800.,
+            plunge_rate: 200.,
+            depth_per_pass: 1.,
+            thickness: 3.,
+            tool_diameter: 0.,
+            machine: Machine::Router {
+                spindle_speed: 12000.,
+                safe_height: 5.,
+            },
+        }
+    }
+}
+
+impl GcodeConfig {
+    /// Checks that the passes go through the material in a finite number
+    pub fn check(&self) -> Result<(), GcodeError> {
+        let positive = |v: f64| v.is_finite() && v > 0.;
+        if !positive(self.depth_per_pass) {
+            return Err(GcodeError::DepthPerPass(self.depth_per_pass));
+        }
+        if !positive(self.thickness) {
+            return Err(GcodeError::Thickness(self.thickness));
+        }
+        Ok(())
+    }
+
+    /// Number of passes needed to go through the material
+    pub fn passes(&self) -> usize {
+        ((self.thickness / self.depth_per_pass) - 1e-9)
+            .ceil()
+            .max(1.) as usize
+    }
+
+    /// Depth reached by a pass, numbered from 1
+    fn depth(&self, pass: usize) -> f64 {
+        (pass as f64 * self.depth_per_pass).min(self.thickness)
+    }
+
+    /// Offsets polygons by half the tool diameter, away from the material: exteriors
+    /// grow, holes shrink.  Holes smaller than the tool disappear.  The precision
+    /// is chosen from the extent of each polygon.
+    pub fn compensate(&self, polygons: &[Polygon]) -> Vec<Polygon> {
+        if self.tool_diameter <= 0. {
+            return polygons.to_vec();
+        }
+        polygons
+            .iter()
+            .flat_map(|p| {
+                let context = GeometryContext::for_geometry(p);
+                p.orient(Direction::Default).offset(
+                    self.tool_diameter / 2.,
+                    context.round(),
+                    EndType::ClosedPolygon,
+                    context.scale,
+                )
+            })
+            .collect()
+    }
+}
+
+fn move_to(gcode: &mut String, command: &str, c: Coord) {
+    writeln!(gcode, "{} X{:.3} Y{:.3}", command, c.x, c.y).unwrap();
+}
+
+/// Writes the passes over a cut.  A whole ring is followed round once per pass;
+/// an open part of a ring is started again from its first point.
+fn write_cut(gcode: &mut String, cut: &Cut, config: &GcodeConfig) {
+    let Some(start) = cut.line.0.first() else {
+        return;
+    };
+    match config.machine {
+        Machine::Router { safe_height, .. } => {
+            writeln!(gcode, "G0 Z{:.3}", safe_height).unwrap();
+            move_to(gcode, "G0", *start);
+            for pass in 1..=config.passes() {
+                if pass > 1 && !cut.closed {
+                    writeln!(gcode, "G0 Z{:.3}", safe_height).unwrap();
+                    move_to(gcode, "G0", *start);
+                }
+                writeln!(
+                    gcode,
+                    "G1 Z{:.3} F{:.0}",
+                    -config.depth(pass),
+                    config.plunge_rate
+                )
+                .unwrap();
+                writeln!(gcode, "F{:.0}", config.feed_rate).unwrap();
+                for c in cut.line.0.iter().skip(1) {
+                    move_to(gcode, "G1", *c);
+                }
+            }
+        }
+        Machine::Laser { .. } => {
+            move_to(gcode, "G0", *start);
+            for pass in 1..=config.passes() {
+                if pass > 1 && !cut.closed {
+                    move_to(gcode, "G0", *start);
+                }
+                writeln!(gcode, "F{:.0}", config.feed_rate).unwrap();
+                for c in cut.line.0.iter().skip(1) {
+                    move_to(gcode, "G1", *c);
+                }
+            }
+        }
+    }
+}
+
+/// Writes a cut path as G-code, in millimeters and absolute coordinates
+pub fn cut_path_to_gcode(path: &CutPath, config: &GcodeConfig) -> Result<String, GcodeError> {
+    config.check()?;
+    let mut gcode = String::new();
+    writeln!(gcode, "G21\nG90").unwrap();
+    match config.machine {
+        Machine::Router { spindle_speed, .. } => {
+            writeln!(gcode, "M3 S{:.0}", spindle_speed).unwrap()
+        }
+        // In laser mode, the laser is off during the G0 moves
+        Machine::Laser { power, dynamic } => {
+            writeln!(gcode, "{} S{}", if dynamic { "M4" } else { "M3" }, power).unwrap()
+        }
+    }
+    for cut in path.cuts.iter() {
+        write_cut(&mut gcode, cut, config);
+    }
+    if let Machine::Router { safe_height, .. } = config.machine {
+        writeln!(gcode, "G0 Z{:.3}", safe_height).unwrap();
+    }
+    writeln!(gcode, "M5\nG0 X0 Y0\nM2").unwrap();
+    Ok(gcode)
+}
+
+/// Writes polygons as G-code, compensated for the tool, the rings in cut order
+pub fn polygons_to_gcode(polygons: &[Polygon], config: &GcodeConfig) -> Result<String, GcodeError> {
+    let path = CutPath::plan(&config.compensate(polygons), coord! {x: 0., y: 0.});
+    cut_path_to_gcode(&path, config)
+}
+
+pub fn save_cut_path_as_gcode(
+    path: &CutPath,
+    config: &GcodeConfig,
+    filename: &str,
+) -> Result<(), GcodeError> {
+    let gcode = cut_path_to_gcode(path, config)?;
+    fs::write(filename, gcode).expect("Unable to write file");
+    Ok(())
+}
+
+pub fn save_polygons_as_gcode(
+    polygons: &[Polygon],
+    config: &GcodeConfig,
+    filename: &str,
+) -> Result<(), GcodeError> {
+    let gcode = polygons_to_gcode(polygons, config)?;
+    fs::write(filename, gcode).expect("Unable to write file");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::{
+        assert_valid, holes_either_way, make_base, make_hexagon_config, nothing,
+    };
+    use geo_types::{polygon, LineString};
+
+    /// A cutting move, at a depth
+    struct Segment {
+        from: Coord,
+        to: Coord,
+        depth: f64,
+    }
+
+    /// Runs the G-code on a simple machine, and returns the moves that cut: those
+    /// below the surface for a router, with the laser on for a laser
+    fn simulate(gcode: &str, laser: bool) -> Vec<Segment> {
+        let mut position = coord! {x: 0., y: 0.};
+        let (mut z, mut power) = (0., 0.);
+        let mut segments = Vec::new();
+        for line in gcode.lines() {
+            let words: Vec<(char, f64)> = line
+                .split_whitespace()
+                .filter_map(|w| {
+                    let mut chars = w.chars();
+                    let letter = chars.next()?;
+                    chars.as_str().parse().ok().map(|v| (letter, v))
+                })
+                .collect();
+            let value = |letter| words.iter().find(|(l, _)| *l == letter).map(|(_, v)| *v);
+            let command = line.split_whitespace().next().unwrap_or("");
+            match command {
+                "M3" | "M4" => power = value('S').unwrap(),
+                "M5" => power = 0.,
+                "G0" | "G1" => {
+                    let to = coord! {
+                        x: value('X').unwrap_or(position.x),
+                        y: value('Y').unwrap_or(position.y),
+                    };
+                    z = value('Z').unwrap_or(z);
+                    let cutting = command == "G1" && if laser { power > 0. } else { z < 0. };
+                    if cutting && to != position {
+                        segments.push(Segment {
+                            from: position,
+                            to,
+                            depth: -z,
+                        });
+                    }
+                    position = to;
+                }
+                _ => {}
+            }
+        }
+        segments
+    }
+
+    /// Whether every side of a ring is cut at the given depth
+    fn retraced(ring: &LineString, segments: &[Segment], depth: f64) -> bool {
+        let close = |a: Coord, b: Coord| (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3;
+        ring.lines().all(|l| {
+            segments.iter().any(|s| {
+                (s.depth - depth).abs() < 1e-9
+                    && ((close(s.from, l.start) && close(s.to, l.end))
+                        || (close(s.from, l.end) && close(s.to, l.start)))
+            })
+        })
+    }
+
+    #[test]
+    fn router_retraces_hexagon() {
+        let hexa = Hexagon::new_at_base(&make_base(), &make_hexagon_config());
+        let config = GcodeConfig {
+            depth_per_pass: 1.2,
+            ..GcodeConfig::default()
+        };
+
+        let gcode = polygons_to_gcode(std::slice::from_ref(&hexa.polygon), &config).unwrap();
+        let segments = simulate(&gcode, false);
+
+        // Three passes, the last one stopping at the thickness
+        assert_eq!(3, config.passes());
+        for depth in [1.2, 2.4, 3.] {
+            assert!(retraced(hexa.polygon.exterior(), &segments, depth));
+            for hole in hexa.polygon.interiors() {
+                assert!(retraced(hole, &segments, depth));
+            }
+        }
+        assert!(segments.iter().all(|s| s.depth <= 3.));
+        assert_valid(&hexa.polygon, holes_either_way);
+        save_polygons_as_gcode(
+            std::slice::from_ref(&hexa.polygon),
+            &config,
+            "test_figures/hexa1.gcode",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn laser_with_kerf() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.), (x: 0., y: 10.)],
+            interiors: [[(x: 2., y: 2.), (x: 6., y: 2.), (x: 6., y: 6.), (x: 2., y: 6.)]],
+        );
+        let config = GcodeConfig {
+            thickness: 1.,
+            tool_diameter: 0.2,
+            machine: Machine::Laser {
+                power: 800,
+                dynamic: true,
+            },
+            ..GcodeConfig::default()
+        };
+
+        let gcode = polygons_to_gcode(std::slice::from_ref(&square), &config).unwrap();
+        let segments = simulate(&gcode, true);
+
+        assert!(gcode.contains("M4 S800"));
+        assert!(!gcode.contains("Z"));
+        // The hole is cut 0.1 inside, its corners stay sharp
+        let hole = LineString::from(vec![
+            (2.1, 2.1),
+            (5.9, 2.1),
+            (5.9, 5.9),
+            (2.1, 5.9),
+            (2.1, 2.1),
+        ]);
+        assert!(retraced(&hole, &segments, 0.));
+        // and the exterior is cut 0.1 outside
+        let xs = segments.iter().flat_map(|s| [s.from.x, s.to.x]);
+        let (min, max) = xs.fold((f64::MAX, f64::MIN), |(a, b), x| (a.min(x), b.max(x)));
+        assert!((min + 0.1).abs() < 1e-3 && (max - 10.1).abs() < 1e-3);
+        for compensated in config.compensate(&[square]) {
+            assert_valid(&compensated, nothing);
+        }
+    }
+
+    #[test]
+    fn endless_passes() {
+        let square = polygon![(x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.), (x: 0., y: 10.)];
+        for depth_per_pass in [0., -1., f64::NAN, f64::INFINITY] {
+            let config = GcodeConfig {
+                depth_per_pass,
+                ..GcodeConfig::default()
+            };
+            let error = polygons_to_gcode(std::slice::from_ref(&square), &config).unwrap_err();
+            assert!(matches!(error, GcodeError::DepthPerPass(_)));
+        }
+        let config = GcodeConfig {
+            thickness: 0.,
+            ..GcodeConfig::default()
+        };
+        assert_eq!(
+            Err(GcodeError::Thickness(0.)),
+            polygons_to_gcode(&[square], &config)
+        );
+    }
+}
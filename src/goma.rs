@@ -28,24 +28,24 @@ impl KumikoConfigTrait for GomaConfig {
     }
 }
 
-impl<'b> KumikoFigure<GomaConfig> for Goma<'b> {
+impl KumikoFigure<GomaConfig> for Goma {
     fn polygon(&self) -> &Polygon {
         &self.polygon
     }
     fn points(&self) -> &[Point] {
-        return &self.points;
+        &self.points
     }
 }
 
-pub struct Goma<'b> {
+#[derive(Clone)]
+pub struct Goma {
     polygon: Polygon,
     pub rays: MultiLineString,
     points: Vec<Point>,
-    base: &'b Base,
 }
 
-impl<'b> Goma<'b> {
-    pub fn new_at_base(base: &'b Base, config: &dyn GomaConfigTrait) -> Self {
+impl Goma {
+    pub fn new_at_base(base: &Base, config: &dyn GomaConfigTrait) -> Self {
         //    fn new_inside_box(pts: &MultiPoint, space: f64, config: &KumikoConfig) -> Self {
         let pa = base.origin;
         let pb = base.origin + base.u;
@@ -97,7 +97,6 @@ impl<'b> Goma<'b> {
             polygon: poly,
             points: vec![pa, pb, pc],
             rays: Goma::make_rays(vec![&lab2lr.r, &lbc2lr.r, &lca2lr.r]),
-            base,
         }
     }
 
@@ -113,6 +112,7 @@ impl<'b> Goma<'b> {
     }
 }
 
+#[derive(Clone)]
 pub struct GomaHexagon {
     polygon: Polygon,
     points: Vec<Point>,
@@ -123,7 +123,7 @@ impl KumikoFigure<GomaConfig> for GomaHexagon {
         &self.polygon
     }
     fn points(&self) -> &[Point] {
-        return &self.points;
+        &self.points
     }
 }
 
@@ -136,7 +136,6 @@ impl GomaHexagon {
         let mut coords: Vec<Coord> = Vec::new();
         let mut polygons: Vec<LineString> = Vec::new();
         (0..6)
-            .into_iter()
             .map(|i: i32| {
                 let phi = (i as f64) * 60.;
                 AffineTransform::rotate(phi, base.origin)
@@ -147,35 +146,35 @@ impl GomaHexagon {
                 coords.push(p.0);
                 let interiors: &[LineString] = unit.polygon.interiors();
                 interiors
-                    .into_iter()
+                    .iter()
                     .for_each(|ls| polygons.push(ls.affine_transform(&t)));
             });
 
         let poly: Polygon = Polygon::new(LineString::new(coords), polygons);
         GomaHexagon {
             polygon: poly,
-            points: points,
+            points,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::frame::{Frame, SimpleFrame};
     use crate::grid::HoneycombGrid;
     use crate::kumiko::{Base, KumikoConfig, KumikoFigure};
     use crate::oglines::points2geometry;
-    use crate::operations::intersect;
     use crate::svg::{save_polygon_as_svg, write_svg};
-    use geo::Translate;
+    use crate::test_support::{assert_valid, holes_either_way, nothing};
+    use geo::Area;
     use geo_svg::{Color, ToSvg};
     use geo_types::point;
 
     fn make_config() -> GomaConfig {
-        let config = GomaConfig {
+        GomaConfig {
             space: 0.75,
             parent: KumikoConfig::default(),
-        };
-        config
+        }
     }
     fn make_points() -> [Point; 3] {
         let side: f64 = 4.0;
@@ -212,7 +211,7 @@ mod tests {
             .with_stroke_width(0.01)
             .with_stroke_color(Color::Rgb(100, 0, 200))
             .with_fill_opacity(0.2);
-        let svg_base = goma.base.draw();
+        let svg_base = base.draw();
 
         let svg_figure = goma.draw_figure();
         let svg = svg_figure //
@@ -221,6 +220,8 @@ mod tests {
             .and(svg_base)
             .to_string();
 
+        assert_eq!(7, goma.polygon().interiors().len());
+        assert_valid(goma.polygon(), holes_either_way);
         write_svg(&svg, "test_figures/goma.svg");
     }
 
@@ -246,6 +247,9 @@ mod tests {
             .and(svg_base)
             .to_string();
 
+        // The holes of the six gomas around the center
+        assert_eq!(6 * 7, goma2.polygon().interiors().len());
+        assert_valid(goma2.polygon(), holes_either_way);
         write_svg(&svg, "test_figures/gomahax.svg");
     }
 
@@ -261,30 +265,24 @@ mod tests {
 
         // Makes a list of LineString's
         let figure = make_honeycomb_grid(&base, &phexa);
+        assert_eq!(522, figure.interiors().len());
+        let outer = Polygon::new(figure.exterior().clone(), vec![]);
+        assert!(figure.unsigned_area() < outer.unsigned_area());
+        assert_valid(&figure, nothing);
         save_polygon_as_svg(&figure, "test_figures/goma_plane.svg");
     }
 
     fn make_honeycomb_grid(base: &Base, unit: &Polygon) -> Polygon {
         let dx: f64 = base.u.x();
         let dy = 3. * base.u.y();
+        let (nx, ny) = (4, 5);
 
-        let mut interiors: Vec<LineString> = Vec::new();
-
-        let grid = HoneycombGrid::new(dx, dy, 4, 5);
-        let origins: &Vec<Coord<f64>> = &grid.nodes;
-
-        //
-        for origin in origins {
-            let copy = unit.translate(origin.x, origin.y);
-            copy.interiors().iter().for_each(|p| {
-                interiors.push(p.clone());
-            });
-        }
+        let mut grid = HoneycombGrid::new(dx, dy, nx, ny);
+        let interiors = grid.fill_with_unit(unit);
 
-        //    let figure = clip(interiors, contour_line, clipping_line);
-        let contour_line = grid.contour_large();
-        let clipping_line = grid.contour_small();
-        let figure = intersect(interiors, contour_line, clipping_line);
-        figure
+        let width: f64 = (2 * nx - 2) as f64 * dx;
+        let height: f64 = (ny - 1) as f64 * dy;
+        let frame = SimpleFrame::new(point! {x: 0., y: 0.}, width, height, 1.0);
+        frame.frame(&interiors)
     }
 }
@@ -1,5 +1,7 @@
 use geo::{Point, Polygon, Translate};
 use geo_types::{coord, Coord, LineString};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use crate::operations::{clip, intersect};
 
@@ -51,18 +53,20 @@ impl HoneycombGrid {
         grid
     }
 
+    /// Copies the holes of the unit at each node, in the order of the nodes.
+    /// With the `rayon` feature, the copies are made in parallel.
     pub fn fill_with_unit(&mut self, unit: &Polygon) -> Vec<LineString> {
-        let mut interiors: Vec<LineString> = Vec::new();
-
-        let origins: &Vec<Coord<f64>> = &self.nodes;
+        let copy = |origin: &Coord<f64>| {
+            unit.interiors()
+                .iter()
+                .map(|p| p.translate(origin.x, origin.y))
+                .collect::<Vec<LineString>>()
+        };
 
-        //
-        for origin in origins {
-            let copy = unit.translate(origin.x, origin.y);
-            copy.interiors().iter().for_each(|p| {
-                interiors.push(p.clone());
-            });
-        }
+        #[cfg(feature = "rayon")]
+        let interiors = self.nodes.par_iter().flat_map_iter(copy).collect();
+        #[cfg(not(feature = "rayon"))]
+        let interiors = self.nodes.iter().flat_map(copy).collect();
         interiors
     }
 }
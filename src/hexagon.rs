@@ -5,14 +5,15 @@ use geo::{polygon, AffineOps, AffineTransform};
 use geo_svg::ToSvg;
 use geo_types::{Coord, LineString, Point, Polygon};
 
+#[derive(Clone)]
 pub struct Hexagon {
     pub polygon: Polygon,
     points: Vec<Point>,
 }
 
 pub struct HexagonConfig {
-    space: f64,
-    parent: KumikoConfig,
+    pub(crate) space: f64,
+    pub(crate) parent: KumikoConfig,
 }
 pub trait HexagonConfigTrait:
     KumikoConfigTrait + EventailConfigTrait + TriskellConfigTrait
@@ -157,6 +158,7 @@ mod tests {
     use crate::grid::HoneycombGrid;
     use crate::kumiko::KumikoConfig;
     use crate::svg::save_polygon_as_svg;
+    use crate::test_support::{assert_valid, holes_either_way, nothing};
     use geo_types::point;
 
     fn make_base() -> Base {
@@ -196,6 +198,7 @@ mod tests {
         // Extract a polygon from the hexagon
         let phexa = hexa.polygon;
         save_polygon_as_svg(&phexa, "test_figures/hexa1.svg");
+        assert_valid(&phexa, holes_either_way);
 
         // Repeat polygon on a honeycomb grid
         let dx: f64 = base.u.x();
@@ -210,5 +213,6 @@ mod tests {
         let frame = SimpleFrame::new(point! {x:0.0, y:0.0}, width, height, 1.0);
         let figure = frame.frame(&inner_figure);
         save_polygon_as_svg(&figure, "test_figures/plane.svg");
+        assert_valid(&figure, nothing);
     }
 }
@@ -0,0 +1,318 @@
+use geo::{Area, BoundingRect};
+use geo_svg::{Color, Style, Svg, ToSvg, ToSvgStr};
+use geo_types::{Coord, MultiLineString, MultiPoint, Point, Polygon};
+use std::fmt::Write;
+use std::fs;
+
+use crate::frame::Frame;
+use crate::kumiko::Base;
+use crate::svg::escape_xml;
+
+/// A construction layer, that can be shown or hidden
+struct Layer {
+    id: String,
+    label: String,
+    content: String,
+}
+
+/// A single HTML file to look at a figure: the svg of the figure and its
+/// construction layers, with a few lines of javascript to zoom (mouse wheel),
+/// pan (drag), show or hide the layers, and show the index and area of the hole
+/// under the mouse.
+pub struct HtmlPreview {
+    title: String,
+    /// Min x, min y, width and height of the figure
+    bounds: (f64, f64, f64, f64),
+    figure: String,
+    layers: Vec<Layer>,
+}
+
+fn points_to_svg(points: MultiPoint, color: Color) -> String {
+    points
+        .to_svg()
+        .with_radius(0.03)
+        .with_stroke_width(0.01)
+        .with_stroke_color(color)
+        .with_fill_color(color)
+        .svg_str()
+}
+
+impl HtmlPreview {
+    pub fn new(title: &str, polygon: &Polygon) -> Self {
+        let bounds = polygon
+            .bounding_rect()
+            .map(|r| (r.min().x, r.min().y, r.width(), r.height()))
+            .unwrap_or((0., 0., 1., 1.));
+
+        let style = Style {
+            stroke_color: Some(Color::Rgb(200, 0, 0)),
+            stroke_width: Some(0.01),
+            fill: Some(Color::Rgb(200, 0, 0)),
+            fill_opacity: Some(0.2),
+            ..Style::default()
+        };
+        let mut figure = Polygon::new(polygon.exterior().clone(), vec![]).to_svg_str(&style);
+        let hole_style = Style {
+            fill: Some(Color::Named("white")),
+            fill_opacity: Some(1.),
+            ..style
+        };
+        for (index, hole) in polygon.interiors().iter().enumerate() {
+            let hole = Polygon::new(hole.clone(), vec![]);
+            let path = hole.to_svg_str(&hole_style).replacen(
+                "<path ",
+                &format!(
+                    r#"<path class="hole" data-index="{}" data-area="{:.4}" "#,
+                    index,
+                    hole.unsigned_area()
+                ),
+                1,
+            );
+            figure.push_str(&path);
+        }
+        HtmlPreview {
+            title: title.to_string(),
+            bounds,
+            figure,
+            layers: vec![],
+        }
+    }
+
+    /// Adds a layer, hidden or shown with a check box
+    pub fn with_layer(mut self, id: &str, label: &str, svg: &Svg) -> Self {
+        self.layers.push(Layer {
+            id: id.to_string(),
+            label: label.to_string(),
+            content: svg.svg_str(),
+        });
+        self
+    }
+
+    /// The arrows of a base, as drawn by `Base::draw`
+    pub fn with_base(self, base: &Base) -> Self {
+        self.with_layer("base", "Base arrows", &base.draw())
+    }
+
+    pub fn with_rays(self, rays: &MultiLineString) -> Self {
+        let svg = rays
+            .to_svg()
+            .with_stroke_width(0.01)
+            .with_stroke_color(Color::Rgb(0, 120, 200));
+        self.with_layer("rays", "Rays", &svg)
+    }
+
+    /// The points a figure is built from
+    pub fn with_points(mut self, points: &[Point]) -> Self {
+        self.layers.push(Layer {
+            id: "points".to_string(),
+            label: "Points".to_string(),
+            content: points_to_svg(MultiPoint::new(points.to_vec()), Color::Rgb(100, 0, 200)),
+        });
+        self
+    }
+
+    /// The nodes of a grid, where the units are placed
+    pub fn with_nodes(mut self, nodes: &[Coord]) -> Self {
+        let points = MultiPoint::new(nodes.iter().map(|c| Point::from(*c)).collect());
+        self.layers.push(Layer {
+            id: "nodes".to_string(),
+            label: "Grid nodes".to_string(),
+            content: points_to_svg(points, Color::Rgb(0, 150, 0)),
+        });
+        self
+    }
+
+    /// The inner and outer contours of a frame
+    pub fn with_frame(self, frame: &dyn Frame) -> Self {
+        let contours = MultiLineString::new(vec![frame.inner_contour(), frame.outer_contour()]);
+        let svg = contours
+            .to_svg()
+            .with_stroke_width(0.02)
+            .with_stroke_color(Color::Rgb(0, 0, 200));
+        self.with_layer("frame", "Frame contours", &svg)
+    }
+
+    pub fn to_html(&self) -> String {
+        let (x, y, w, h) = self.bounds;
+        let margin = 0.05 * w.max(h);
+        let viewbox = format!(
+            "{} {} {} {}",
+            x - margin,
+            y - margin,
+            w + 2. * margin,
+            h + 2. * margin
+        );
+
+        let mut toggles = String::new();
+        let mut layers = String::new();
+        for layer in self.layers.iter() {
+            writeln!(
+                toggles,
+                r#"<label><input type="checkbox" data-layer="layer-{id}" checked> {label}</label>"#,
+                id = escape_xml(&layer.id),
+                label = escape_xml(&layer.label)
+            )
+            .unwrap();
+            writeln!(
+                layers,
+                r#"<g id="layer-{}">{}</g>"#,
+                escape_xml(&layer.id),
+                layer.content
+            )
+            .unwrap();
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ margin: 0; font-family: sans-serif; }}
+#controls {{ position: fixed; top: 0; left: 0; padding: 8px; background: rgba(255, 255, 255, 0.9); }}
+#controls label {{ display: block; }}
+#tooltip {{ position: fixed; display: none; padding: 2px 6px; background: #333; color: #fff; font-size: 12px; pointer-events: none; }}
+svg {{ width: 100vw; height: 100vh; cursor: grab; }}
+.hole:hover {{ fill: #ffd27f; }}
+</style>
+</head>
+<body>
+<div id="controls">
+<strong>{title}</strong>
+{toggles}<button id="reset">Reset view</button>
+</div>
+<div id="tooltip"></div>
+<svg id="figure" xmlns="http://www.w3.org/2000/svg" preserveAspectRatio="xMidYMid meet" viewBox="{viewbox}">
+<g id="layer-figure">{figure}</g>
+{layers}</svg>
+<script>
+const svg = document.getElementById("figure");
+const initial = svg.getAttribute("viewBox");
+let box = initial.split(" ").map(Number);
+const show = () => svg.setAttribute("viewBox", box.join(" "));
+const toSvg = (e) => {{
+    const r = svg.getBoundingClientRect();
+    const s = Math.max(box[2] / r.width, box[3] / r.height);
+    return [s, box[0] + (e.clientX - r.left - (r.width - box[2] / s) / 2) * s,
+            box[1] + (e.clientY - r.top - (r.height - box[3] / s) / 2) * s];
+}};
+svg.addEventListener("wheel", (e) => {{
+    e.preventDefault();
+    const [, x, y] = toSvg(e);
+    const k = e.deltaY < 0 ? 0.8 : 1.25;
+    box = [x - (x - box[0]) * k, y - (y - box[1]) * k, box[2] * k, box[3] * k];
+    show();
+}});
+let drag = null;
+svg.addEventListener("mousedown", (e) => {{ drag = [e.clientX, e.clientY, box[0], box[1]]; }});
+window.addEventListener("mouseup", () => {{ drag = null; }});
+window.addEventListener("mousemove", (e) => {{
+    if (drag) {{
+        const [s] = toSvg(e);
+        box[0] = drag[2] - (e.clientX - drag[0]) * s;
+        box[1] = drag[3] - (e.clientY - drag[1]) * s;
+        show();
+    }}
+}});
+document.getElementById("reset").addEventListener("click", () => {{
+    box = initial.split(" ").map(Number);
+    show();
+}});
+document.querySelectorAll("input[data-layer]").forEach((input) => {{
+    input.addEventListener("change", () => {{
+        document.getElementById(input.dataset.layer).style.display = input.checked ? "" : "none";
+    }});
+}});
+const tooltip = document.getElementById("tooltip");
+document.querySelectorAll(".hole").forEach((hole) => {{
+    hole.addEventListener("mousemove", (e) => {{
+        tooltip.textContent = "hole " + hole.dataset.index + ", area " + hole.dataset.area;
+        tooltip.style.left = e.clientX + 12 + "px";
+        tooltip.style.top = e.clientY + 12 + "px";
+        tooltip.style.display = "block";
+    }});
+    hole.addEventListener("mouseleave", () => {{ tooltip.style.display = "none"; }});
+}});
+</script>
+</body>
+</html>
+"#,
+            title = escape_xml(&self.title),
+            toggles = toggles,
+            viewbox = viewbox,
+            figure = self.figure,
+            layers = layers,
+        )
+    }
+}
+
+pub fn save_preview_as_html(preview: &HtmlPreview, filename: &str) {
+    fs::write(filename, preview.to_html()).expect("Unable to write file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::SimpleFrame;
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::kumiko::KumikoFigure;
+    use crate::test_support::{
+        assert_valid, holes_either_way, make_base, make_hexagon_config, make_triskell_base,
+        make_triskell_config, nothing,
+    };
+    use crate::triskell::Triskell;
+    use geo_types::point;
+
+    #[test]
+    fn triskell_preview() {
+        let base = make_triskell_base();
+        let triskell = Triskell::new_at_base(&base, &make_triskell_config());
+
+        let html = HtmlPreview::new("Triskell <3 & co", triskell.polygon())
+            .with_base(&base)
+            .with_rays(&triskell.rays)
+            .with_points(triskell.points())
+            .to_html();
+
+        let holes = triskell.polygon().interiors().len();
+        assert_eq!(holes, html.matches(r#"class="hole""#).count());
+        assert!(html.contains(r#"data-index="0" data-area=""#));
+        for layer in ["layer-base", "layer-rays", "layer-points"] {
+            assert!(html.contains(&format!(r#"<g id="{layer}">"#)));
+            assert!(html.contains(&format!(r#"data-layer="{layer}""#)));
+        }
+        assert!(!html.contains("layer-nodes"));
+        assert!(html.contains("<title>Triskell &lt;3 &amp; co</title>"));
+        assert_valid(triskell.polygon(), holes_either_way);
+    }
+
+    #[test]
+    fn plane_preview() {
+        let base = make_base();
+        let hexa = Hexagon::new_at_base(&base, &make_hexagon_config());
+        let (dx, dy) = (base.u.x(), 3. * base.u.y());
+        let (nx, ny) = (3, 3);
+        let mut grid = HoneycombGrid::new(dx, dy, nx, ny);
+        let inner_figure = grid.fill_with_unit(&hexa.polygon);
+        let width: f64 = (2 * nx - 2) as f64 * dx;
+        let height: f64 = (ny - 1) as f64 * dy;
+        let frame = SimpleFrame::new(point! {x: 0., y: 0.}, width, height, 1.0);
+        let figure = frame.frame(&inner_figure);
+
+        let preview = HtmlPreview::new("Plane", &figure)
+            .with_base(&base)
+            .with_nodes(&grid.nodes)
+            .with_frame(&frame);
+
+        let html = preview.to_html();
+        assert_eq!(
+            figure.interiors().len(),
+            html.matches(r#"class="hole""#).count()
+        );
+        assert_eq!(grid.nodes.len(), html.matches("<circle").count());
+        assert_valid(&figure, nothing);
+        save_preview_as_html(&preview, "test_figures/plane_preview.html");
+    }
+}
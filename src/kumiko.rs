@@ -13,6 +13,7 @@ impl Default for KumikoConfig {
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct Base {
     pub origin: Point,
     pub u: Point,
@@ -83,6 +84,9 @@ impl KumikoConfigTrait for KumikoConfig {
     }
 }
 
+/// A figure owns its polygon and what it copied from its base, so that it can
+/// be stored, returned and sent to other threads.  The trait itself is kept
+/// object safe: the figures derive `Clone` on their own.
 pub trait KumikoFigure<KC: KumikoConfigTrait> {
     //    fn new_at_base(base: &Base, config: &KC) -> Self;
     fn polygon(&self) -> &Polygon;
@@ -124,3 +128,23 @@ pub trait KumikoFigure<KC: KumikoConfigTrait> {
 pub fn mean_point(pa: &Point, pb: &Point) -> Point {
     point! { x: (pb.x()-pa.x())/2., y:(pb.y()-pa.y()) / 2.}
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::eventail::Eventail;
+    use crate::goma::Goma;
+    use crate::hexagon::Hexagon;
+    use crate::seigaiha::Seigaiha;
+    use crate::triskell::Triskell;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn figures_are_send_sync() {
+        assert_send_sync::<Eventail>();
+        assert_send_sync::<Goma>();
+        assert_send_sync::<Hexagon>();
+        assert_send_sync::<Seigaiha>();
+        assert_send_sync::<Triskell>();
+    }
+}
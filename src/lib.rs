@@ -5,10 +5,32 @@ pub mod svg;
 // pub mod asanoha;
 pub mod eventail;
 // pub mod flower;
-// pub mod goma;
+pub mod goma;
 pub mod grid;
 pub mod hexagon;
 pub mod operations;
 pub mod triskell;
 // pub mod tsumiishi;
+pub mod boolean;
+pub mod corners;
+pub mod curves;
+pub mod cutpath;
+pub mod dxf;
 pub mod frame;
+pub mod gcode;
+pub mod html;
+pub mod lightburn;
+pub mod lightburn_import;
+pub mod merge;
+pub mod nest;
+pub mod pdf;
+pub mod precision;
+pub mod raster;
+pub mod registry;
+pub mod seigaiha;
+pub mod split;
+pub mod svg_import;
+pub mod tabs;
+#[cfg(test)]
+mod test_support;
+pub mod validity;
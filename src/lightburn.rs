@@ -0,0 +1,182 @@
+use geo_types::{coord, Coord, Polygon};
+use std::fmt::Write;
+use std::fs;
+
+use crate::curves::{CurvePath, Segment};
+use crate::cutpath::{Cut, CutPath};
+
+/// Writes a cut as a LightBurn path.
+///
+/// Vertices are written as `V<x> <y>` followed by `c0x1c1x1`, which tells that the
+/// vertex has no control points.  The closing vertex of a ring is not repeated,
+/// LightBurn closes the path itself with `LineClosed`.  An open path lists its
+/// lines, as in `L0 1L1 2`.
+fn cut_to_shape(cut: &Cut, cut_index: u8) -> String {
+    let mut coords = cut.line.0.as_slice();
+    if cut.closed && cut.line.is_closed() && coords.len() > 1 {
+        coords = &coords[..coords.len() - 1];
+    }
+    let mut verts = String::new();
+    for c in coords {
+        write!(verts, "V{} {}c0x1c1x1", c.x, c.y).unwrap();
+    }
+    let prims = if cut.closed {
+        String::from("LineClosed")
+    } else {
+        (1..coords.len())
+            .map(|i| format!("L{} {}", i - 1, i))
+            .collect()
+    };
+    format!(
+        r#"    <Shape Type="Path" CutIndex="{cut_index}">
+        <XForm>1 0 0 1 0 0</XForm>
+        <VertList>{verts}</VertList>
+        <PrimList>{prims}</PrimList>
+    </Shape>
+"#
+    )
+}
+
+/// Writes a curve path as a LightBurn path, its arcs replaced with beziers.
+///
+/// A vertex keeps the control point of the bezier leaving it in `c0`, and the
+/// one of the bezier reaching it in `c1`, as in `V1 0c0x1c0y1c1x1`; the
+/// beziers are listed as in `B0 1`.
+fn curve_path_to_shape(path: &CurvePath, cut_index: u8) -> String {
+    let pieces: Vec<Segment> = path
+        .segments
+        .iter()
+        .flat_map(|s| match s {
+            Segment::Line { .. } => vec![*s],
+            _ => s.to_cubics(),
+        })
+        .collect();
+    // The vertices are the starts of the pieces, and the end of an open path
+    let mut coords: Vec<Coord> = pieces.iter().map(|p| p.start()).collect();
+    if !path.is_closed() {
+        coords.extend(pieces.last().map(|p| p.end()));
+    }
+    let mut controls: Vec<(Option<Coord>, Option<Coord>)> = vec![(None, None); coords.len()];
+    let mut prims = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        let j = (i + 1) % coords.len();
+        match *piece {
+            Segment::Cubic { ctrl1, ctrl2, .. } => {
+                controls[i].0 = Some(ctrl1);
+                controls[j].1 = Some(ctrl2);
+                write!(prims, "B{} {}", i, j).unwrap();
+            }
+            _ => write!(prims, "L{} {}", i, j).unwrap(),
+        }
+    }
+    let mut verts = String::new();
+    for (c, (c0, c1)) in coords.iter().zip(controls) {
+        write!(verts, "V{} {}", c.x, c.y).unwrap();
+        for (name, control) in [("c0", c0), ("c1", c1)] {
+            match control {
+                Some(control) => write!(verts, "{name}x{}{name}y{}", control.x, control.y),
+                None => write!(verts, "{name}x1"),
+            }
+            .unwrap();
+        }
+    }
+    format!(
+        r#"    <Shape Type="Path" CutIndex="{cut_index}">
+        <XForm>1 0 0 1 0 0</XForm>
+        <VertList>{verts}</VertList>
+        <PrimList>{prims}</PrimList>
+    </Shape>
+"#
+    )
+}
+
+/// A LightBurn project with a single cut layer, 0
+fn project(shapes: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<LightBurnProject AppVersion="1.4.05" FormatVersion="1" MaterialHeight="0" MirrorX="False" MirrorY="False">
+    <CutSetting type="Cut">
+        <index Value="0"/>
+        <name Value="C00"/>
+    </CutSetting>
+{shapes}</LightBurnProject>
+"#
+    )
+}
+
+/// Writes a cut path as a LightBurn project, every cut on cut layer 0, in cut order
+pub fn cut_path_to_lbrn2(path: &CutPath) -> String {
+    let shapes: String = path.cuts.iter().map(|cut| cut_to_shape(cut, 0)).collect();
+    project(&shapes)
+}
+
+/// Writes curve paths as a LightBurn project, on cut layer 0, keeping their curves
+pub fn curves_to_lbrn2(paths: &[CurvePath]) -> String {
+    let shapes: String = paths
+        .iter()
+        .filter(|path| !path.segments.is_empty())
+        .map(|path| curve_path_to_shape(path, 0))
+        .collect();
+    project(&shapes)
+}
+
+/// Writes polygons as a LightBurn project, every ring being a closed path on cut layer 0.
+/// The shapes are written in cut order.
+pub fn polygons_to_lbrn2(polygons: &[Polygon]) -> String {
+    cut_path_to_lbrn2(&CutPath::plan(polygons, coord! {x: 0., y: 0.}))
+}
+
+pub fn save_cut_path_as_lbrn2(path: &CutPath, filename: &str) {
+    fs::write(filename, cut_path_to_lbrn2(path)).expect("Unable to write file");
+}
+
+pub fn save_curves_as_lbrn2(paths: &[CurvePath], filename: &str) {
+    fs::write(filename, curves_to_lbrn2(paths)).expect("Unable to write file");
+}
+
+pub fn save_polygons_as_lbrn2(polygons: &[Polygon], filename: &str) {
+    fs::write(filename, polygons_to_lbrn2(polygons)).expect("Unable to write file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightburn_import::read_lbrn2;
+    use crate::test_support::{assert_valid, holes_either_way, make_drop, nothing};
+    use geo::Area;
+    use geo_types::polygon;
+
+    #[test]
+    fn square_with_hole() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.), (x: 0., y: 4.)],
+            interiors: [[(x: 1., y: 1.), (x: 2., y: 1.), (x: 2., y: 2.)]],
+        );
+
+        assert_valid(&square, holes_either_way);
+        let lbrn = polygons_to_lbrn2(&[square]);
+
+        assert_eq!(2, lbrn.matches("<Shape ").count());
+        assert!(
+            lbrn.contains("<VertList>V0 0c0x1c1x1V4 0c0x1c1x1V4 4c0x1c1x1V0 4c0x1c1x1</VertList>")
+        );
+        assert!(lbrn.contains("<VertList>V1 1c0x1c1x1V2 1c0x1c1x1V2 2c0x1c1x1</VertList>"));
+    }
+
+    #[test]
+    fn drop_as_beziers() {
+        let drop = make_drop();
+
+        let lbrn = curves_to_lbrn2(std::slice::from_ref(&drop));
+
+        // The half circle is two beziers
+        assert!(lbrn.contains("<PrimList>B0 1B1 2B2 3L3 0</PrimList>"));
+        assert!(lbrn.contains("<VertList>V1 0c0x1c0y0.55228474983079"));
+        let layers = read_lbrn2(&lbrn).unwrap();
+        assert_eq!(1, layers[0].lines.len());
+        assert!(layers[0].lines[0].is_closed());
+        let area = Polygon::new(layers[0].lines[0].clone(), vec![]).unsigned_area();
+        assert!((drop.to_polygon(1e-4).unsigned_area() - area).abs() < 1e-2);
+        assert_valid(&layers[0].polygons()[0], nothing);
+    }
+}
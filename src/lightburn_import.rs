@@ -0,0 +1,623 @@
+use geo::{AffineOps, AffineTransform, Contains};
+use geo_types::{coord, Coord, LineString, Polygon};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::svg_import::{cubic, ring_area};
+
+/// Number of segments of an ellipse shape
+const ELLIPSE_SEGMENTS: usize = 64;
+
+#[derive(Debug, PartialEq)]
+pub enum LightBurnImportError {
+    /// The document is not well formed xml
+    Xml(String),
+    /// A vertex list can't be read
+    VertList(String),
+    /// A primitive list can't be read
+    PrimList(String),
+    /// A shape refers to the vertex or primitive list of a shape not read yet
+    MissingList(u32),
+    /// The cut index of a shape is not a layer number, from 0 to 255
+    CutIndex(String),
+}
+
+impl fmt::Display for LightBurnImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LightBurnImportError::Xml(e) => write!(f, "invalid project: {}", e),
+            LightBurnImportError::VertList(v) => write!(f, "invalid vertex list: {}", v),
+            LightBurnImportError::PrimList(p) => write!(f, "invalid primitive list: {}", p),
+            LightBurnImportError::MissingList(id) => write!(f, "no list with id {}", id),
+            LightBurnImportError::CutIndex(i) => write!(f, "invalid cut index: {}", i),
+        }
+    }
+}
+
+/// The shapes of a cut layer of a LightBurn project
+#[derive(Debug)]
+pub struct CutLayer {
+    pub index: u8,
+    /// Name of the cut setting, if the project has one for this layer
+    pub name: Option<String>,
+    /// Closed paths are closed line strings
+    pub lines: Vec<LineString>,
+}
+
+impl CutLayer {
+    /// Polygons made of the closed paths of the layer.
+    ///
+    /// From the largest ring down, a ring inside the exterior of a polygon is a
+    /// hole of this polygon, otherwise it is the exterior of a new polygon.
+    pub fn polygons(&self) -> Vec<Polygon> {
+        let mut rings: Vec<&LineString> = self
+            .lines
+            .iter()
+            .filter(|l| l.is_closed() && l.0.len() > 3)
+            .collect();
+        rings.sort_by(|a, b| ring_area(b).total_cmp(&ring_area(a)));
+
+        let mut polygons: Vec<Polygon> = vec![];
+        for ring in rings {
+            let outer = polygons
+                .iter_mut()
+                .find(|p| Polygon::new(p.exterior().clone(), vec![]).contains(ring));
+            match outer {
+                Some(polygon) => polygon.interiors_push(ring.clone()),
+                None => polygons.push(Polygon::new(ring.clone(), vec![])),
+            }
+        }
+        polygons
+    }
+}
+
+/// A vertex and its two control points, used by the bezier primitives
+#[derive(Clone, Copy, Debug)]
+struct Vertex {
+    at: Coord,
+    c0: Option<Coord>,
+    c1: Option<Coord>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Prim {
+    Line(usize, usize),
+    Bezier(usize, usize),
+}
+
+/// The primitives of a shape: `LineClosed` joins all the vertices
+#[derive(Clone, Debug)]
+enum PrimList {
+    LineClosed,
+    Prims(Vec<Prim>),
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    XForm,
+    VertList,
+    PrimList,
+}
+
+/// A `<Shape>` being read
+struct ShapeState {
+    kind: String,
+    cut_index: u8,
+    vert_id: Option<u32>,
+    prim_id: Option<u32>,
+    xform: AffineTransform,
+    verts: Option<Vec<Vertex>>,
+    prims: Option<PrimList>,
+    attributes: HashMap<String, String>,
+}
+
+impl ShapeState {
+    fn new(start: &BytesStart) -> Result<Self, LightBurnImportError> {
+        let mut attributes = HashMap::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| LightBurnImportError::Xml(e.to_string()))?;
+            let value = attribute
+                .unescape_value()
+                .map_err(|e| LightBurnImportError::Xml(e.to_string()))?;
+            attributes.insert(
+                String::from_utf8_lossy(attribute.key.as_ref()).to_string(),
+                value.to_string(),
+            );
+        }
+        let id = |name: &str| attributes.get(name).and_then(|v| v.parse().ok());
+        let cut_index = match attributes.get("CutIndex") {
+            Some(v) => v
+                .parse()
+                .map_err(|_| LightBurnImportError::CutIndex(v.clone()))?,
+            None => 0,
+        };
+        Ok(ShapeState {
+            kind: attributes.get("Type").cloned().unwrap_or_default(),
+            cut_index,
+            vert_id: id("VertID"),
+            prim_id: id("PrimID"),
+            xform: AffineTransform::identity(),
+            verts: None,
+            prims: None,
+            attributes,
+        })
+    }
+
+    fn number(&self, name: &str) -> f64 {
+        self.attributes
+            .get(name)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.)
+    }
+}
+
+/// Reads the paths of a LightBurn project, grouped by cut layer.
+///
+/// `Path` shapes are read from their vertex and primitive lists; a shape
+/// without a list uses the list of the first shape with the same `VertID` or
+/// `PrimID`.  `Rect` and `Ellipse` shapes are read from their size, and the
+/// shapes of a `Group` are moved by the transform of the group.  Bezier
+/// primitives are replaced with lines.  Other shapes (text, images) are ignored.
+///
+/// The layers are sorted by cut index.
+pub fn read_lbrn2(lbrn: &str) -> Result<Vec<CutLayer>, LightBurnImportError> {
+    let xml_error = |e: quick_xml::Error| LightBurnImportError::Xml(e.to_string());
+    let mut reader = Reader::from_str(lbrn);
+
+    let mut shapes: Vec<ShapeState> = vec![];
+    let mut field: Option<Field> = None;
+    let mut vert_lists: HashMap<u32, Vec<Vertex>> = HashMap::new();
+    let mut prim_lists: HashMap<u32, PrimList> = HashMap::new();
+    let mut layers: BTreeMap<u8, CutLayer> = BTreeMap::new();
+    let mut names: HashMap<u8, String> = HashMap::new();
+    // Index and name of the cut setting being read
+    let mut setting: Option<(Option<u8>, Option<String>)> = None;
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"Shape" => shapes.push(ShapeState::new(&e)?),
+                b"XForm" => field = Some(Field::XForm),
+                b"VertList" => field = Some(Field::VertList),
+                b"PrimList" => field = Some(Field::PrimList),
+                b"CutSetting" => setting = Some((None, None)),
+                _ => (),
+            },
+            Event::Empty(e) => {
+                if let Some((index, name)) = setting.as_mut() {
+                    let value = e
+                        .try_get_attribute("Value")
+                        .map_err(xml_error)?
+                        .map(|v| String::from_utf8_lossy(&v.value).to_string());
+                    match e.name().as_ref() {
+                        b"index" => *index = value.and_then(|v| v.parse().ok()),
+                        b"name" => *name = value,
+                        _ => (),
+                    }
+                } else if e.name().as_ref() == b"Shape" {
+                    let shape = ShapeState::new(&e)?;
+                    let transform = world_transform(&shapes, &shape);
+                    read_shape(
+                        shape,
+                        transform,
+                        &mut vert_lists,
+                        &mut prim_lists,
+                        &mut layers,
+                    )?;
+                }
+            }
+            Event::Text(t) => {
+                if let (Some(field), Some(shape)) = (field, shapes.last_mut()) {
+                    let text = t.unescape().map_err(xml_error)?;
+                    match field {
+                        Field::XForm => shape.xform = parse_xform(&text)?,
+                        Field::VertList => shape.verts = Some(parse_vert_list(&text)?),
+                        Field::PrimList => shape.prims = Some(parse_prim_list(&text)?),
+                    }
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"Shape" => {
+                    if let Some(shape) = shapes.pop() {
+                        let transform = world_transform(&shapes, &shape);
+                        read_shape(
+                            shape,
+                            transform,
+                            &mut vert_lists,
+                            &mut prim_lists,
+                            &mut layers,
+                        )?;
+                    }
+                }
+                b"XForm" | b"VertList" | b"PrimList" => field = None,
+                b"CutSetting" => {
+                    if let Some((Some(index), Some(name))) = setting.take() {
+                        names.insert(index, name);
+                    }
+                }
+                _ => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(layers
+        .into_values()
+        .map(|mut layer| {
+            layer.name = names.get(&layer.index).cloned();
+            layer
+        })
+        .collect())
+}
+
+/// The transform of a shape, then of the groups it is in
+fn world_transform(groups: &[ShapeState], shape: &ShapeState) -> AffineTransform {
+    groups.iter().rev().fold(shape.xform, |transform, group| {
+        group.xform.compose(&transform)
+    })
+}
+
+fn read_shape(
+    shape: ShapeState,
+    transform: AffineTransform,
+    vert_lists: &mut HashMap<u32, Vec<Vertex>>,
+    prim_lists: &mut HashMap<u32, PrimList>,
+    layers: &mut BTreeMap<u8, CutLayer>,
+) -> Result<(), LightBurnImportError> {
+    let lines = match shape.kind.as_str() {
+        "Path" => {
+            let verts = shared_list(shape.verts, shape.vert_id, vert_lists)?;
+            let prims = shared_list(shape.prims, shape.prim_id, prim_lists)?;
+            path_lines(&verts, &prims)?
+        }
+        "Rect" => {
+            let (w, h) = (shape.number("W") / 2., shape.number("H") / 2.);
+            vec![LineString::from(vec![
+                (-w, -h),
+                (w, -h),
+                (w, h),
+                (-w, h),
+                (-w, -h),
+            ])]
+        }
+        "Ellipse" => {
+            let (rx, ry) = (shape.number("Rx"), shape.number("Ry"));
+            let ellipse: LineString = (0..=ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let t = 2. * PI * (i % ELLIPSE_SEGMENTS) as f64 / ELLIPSE_SEGMENTS as f64;
+                    coord! {x: rx * t.cos(), y: ry * t.sin()}
+                })
+                .collect();
+            vec![ellipse]
+        }
+        _ => return Ok(()),
+    };
+    let layer = layers.entry(shape.cut_index).or_insert_with(|| CutLayer {
+        index: shape.cut_index,
+        name: None,
+        lines: vec![],
+    });
+    layer
+        .lines
+        .extend(lines.into_iter().map(|l| l.affine_transform(&transform)));
+    Ok(())
+}
+
+/// The list of a shape, kept for the next shapes with the same id, or the list
+/// of an earlier shape with the same id
+fn shared_list<T: Clone>(
+    list: Option<T>,
+    id: Option<u32>,
+    lists: &mut HashMap<u32, T>,
+) -> Result<T, LightBurnImportError> {
+    match (list, id) {
+        (Some(list), Some(id)) => {
+            lists.entry(id).or_insert_with(|| list.clone());
+            Ok(list)
+        }
+        (Some(list), None) => Ok(list),
+        (None, Some(id)) => lists
+            .get(&id)
+            .cloned()
+            .ok_or(LightBurnImportError::MissingList(id)),
+        (None, None) => Err(LightBurnImportError::Xml("path without list".to_string())),
+    }
+}
+
+/// Joins the primitives following each other into lines.
+///
+/// A bezier goes from its first vertex, pulled by the `c0` control point of
+/// this vertex, to its second vertex, pulled by the `c1` control point of that
+/// vertex.  A missing control point is the vertex itself.
+fn path_lines(verts: &[Vertex], prims: &PrimList) -> Result<Vec<LineString>, LightBurnImportError> {
+    let prims = match prims {
+        PrimList::LineClosed => {
+            let n = verts.len();
+            (0..n).map(|i| Prim::Line(i, (i + 1) % n)).collect()
+        }
+        PrimList::Prims(prims) => prims.clone(),
+    };
+    let vertex = |i: usize| {
+        verts
+            .get(i)
+            .ok_or_else(|| LightBurnImportError::PrimList(format!("no vertex {}", i)))
+    };
+
+    let mut lines: Vec<LineString> = vec![];
+    let mut line: Vec<Coord> = vec![];
+    // Vertex index of the start and of the end of the current line
+    let mut ends = (0, 0);
+    for prim in prims {
+        let (a, b) = match prim {
+            Prim::Line(a, b) | Prim::Bezier(a, b) => (a, b),
+        };
+        if line.is_empty() || ends.1 != a {
+            if line.len() > 1 {
+                lines.push(LineString::new(std::mem::take(&mut line)));
+            }
+            line = vec![vertex(a)?.at];
+            ends = (a, a);
+        }
+        let (from, to) = (vertex(a)?, vertex(b)?);
+        match prim {
+            Prim::Line(..) => line.push(to.at),
+            Prim::Bezier(..) => line.extend(cubic(
+                from.at,
+                from.c0.unwrap_or(from.at),
+                to.c1.unwrap_or(to.at),
+                to.at,
+            )),
+        }
+        ends.1 = b;
+        if b == ends.0 {
+            lines.push(LineString::new(std::mem::take(&mut line)));
+        }
+    }
+    if line.len() > 1 {
+        lines.push(LineString::new(line));
+    }
+    Ok(lines)
+}
+
+/// Reads `a b c d e f`, the matrix of an svg transform
+fn parse_xform(text: &str) -> Result<AffineTransform, LightBurnImportError> {
+    let m: Vec<f64> = text
+        .split_whitespace()
+        .map(|n| n.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| LightBurnImportError::Xml(format!("invalid transform {}", text)))?;
+    if m.len() != 6 {
+        return Err(LightBurnImportError::Xml(format!(
+            "invalid transform {}",
+            text
+        )));
+    }
+    Ok(AffineTransform::new(m[0], m[2], m[4], m[1], m[3], m[5]))
+}
+
+/// Reads a number at the start of `text`, returns it and the rest of the text
+fn number(text: &str) -> Option<(f64, &str)> {
+    let end = text
+        .char_indices()
+        .find(|&(i, c)| {
+            !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E')
+                && !((c == '-' || c == '+') && (i == 0 || text[..i].ends_with(['e', 'E'])))
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    text[..end].parse().ok().map(|n| (n, &text[end..]))
+}
+
+/// Reads a vertex list, as in `V1 2c0x1c1x3c1y4`.
+///
+/// Every vertex is followed by its control points: `c0x<x>c0y<y>` for the first
+/// one, `c1x<x>c1y<y>` for the second one.  A control point without its y, as
+/// in `c0x1`, is missing.
+fn parse_vert_list(text: &str) -> Result<Vec<Vertex>, LightBurnImportError> {
+    let error = || LightBurnImportError::VertList(text.to_string());
+    let mut verts: Vec<Vertex> = vec![];
+    let mut rest = text.trim();
+    // The x of the control point being read
+    let mut control_x: Option<(bool, f64)> = None;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('V') {
+            let (x, r) = number(r).ok_or_else(error)?;
+            let (y, r) = number(r.trim_start()).ok_or_else(error)?;
+            verts.push(Vertex {
+                at: coord! {x: x, y: y},
+                c0: None,
+                c1: None,
+            });
+            rest = r;
+            continue;
+        }
+        let vertex = verts.last_mut().ok_or_else(error)?;
+        let (tag, r) = ["c0x", "c1x", "c0y", "c1y"]
+            .into_iter()
+            .find_map(|tag| rest.strip_prefix(tag).map(|r| (tag, r)))
+            .ok_or_else(error)?;
+        let (value, r) = number(r).ok_or_else(error)?;
+        match tag {
+            "c0x" => control_x = Some((false, value)),
+            "c1x" => control_x = Some((true, value)),
+            "c0y" | "c1y" => {
+                let second = tag == "c1y";
+                match control_x.take() {
+                    Some((s, x)) if s == second => {
+                        let control = Some(coord! {x: x, y: value});
+                        if second {
+                            vertex.c1 = control;
+                        } else {
+                            vertex.c0 = control;
+                        }
+                    }
+                    _ => return Err(error()),
+                }
+            }
+            _ => return Err(error()),
+        }
+        rest = r.trim_start();
+    }
+    Ok(verts)
+}
+
+/// Reads a primitive list, `LineClosed` or as in `L0 1B1 2L2 0`
+fn parse_prim_list(text: &str) -> Result<PrimList, LightBurnImportError> {
+    let text = text.trim();
+    if text == "LineClosed" {
+        return Ok(PrimList::LineClosed);
+    }
+    let error = || LightBurnImportError::PrimList(text.to_string());
+    let mut prims = vec![];
+    let mut rest = text;
+    while let Some(kind) = rest.chars().next() {
+        let after = &rest[kind.len_utf8()..];
+        let mut indices = after
+            .split(|c: char| c.is_ascii_alphabetic())
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|i| i.parse::<usize>());
+        let (a, b) = match (indices.next(), indices.next(), indices.next()) {
+            (Some(Ok(a)), Some(Ok(b)), None) => (a, b),
+            _ => return Err(error()),
+        };
+        prims.push(match kind {
+            'L' => Prim::Line(a, b),
+            'B' => Prim::Bezier(a, b),
+            _ => return Err(error()),
+        });
+        rest = after.trim_start_matches(|c: char| !c.is_ascii_alphabetic());
+    }
+    Ok(PrimList::Prims(prims))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightburn::polygons_to_lbrn2;
+    use crate::svg::save_polygon_as_svg;
+    use crate::test_support::{assert_valid, windings_as_drawn};
+    use geo::Area;
+    use geo_types::polygon;
+
+    const SHAPES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<LightBurnProject AppVersion="1.4.05" FormatVersion="1">
+    <CutSetting type="Cut">
+        <index Value="2"/>
+        <name Value="Curves"/>
+    </CutSetting>
+    <Shape Type="Group">
+        <XForm>1 0 0 1 10 0</XForm>
+        <Children>
+            <Shape Type="Path" CutIndex="2" VertID="0" PrimID="0">
+                <XForm>1 0 0 1 0 5</XForm>
+                <VertList>V0 0c0x0c0y1c1x1V2 0c0x1c1x2c1y1V2 -1c0x1c1x1</VertList>
+                <PrimList>B0 1L1 2</PrimList>
+            </Shape>
+        </Children>
+    </Shape>
+    <Shape Type="Path" CutIndex="2" VertID="0" PrimID="0">
+        <XForm>1 0 0 1 0 0</XForm>
+    </Shape>
+    <Shape Type="Rect" CutIndex="0" W="4" H="2">
+        <XForm>0 1 -1 0 1 1</XForm>
+    </Shape>
+</LightBurnProject>
+"#;
+
+    #[test]
+    fn read_shapes() {
+        let layers = read_lbrn2(SHAPES).unwrap();
+
+        assert_eq!(
+            vec![0, 2],
+            layers.iter().map(|l| l.index).collect::<Vec<_>>()
+        );
+        // The rectangle, turned a quarter round its center, then moved
+        let rect = &layers[0].lines[0];
+        assert!(rect.is_closed());
+        assert_eq!(coord! {x: 2., y: -1.}, rect.0[0]);
+        assert_eq!(None, layers[0].name);
+
+        let curves = &layers[1];
+        assert_eq!(Some("Curves".to_string()), curves.name);
+        assert_eq!(2, curves.lines.len());
+        // The path of the group, then the same path without the group
+        let (grouped, alone) = (&curves.lines[0], &curves.lines[1]);
+        assert_eq!(coord! {x: 10., y: 5.}, grouped.0[0]);
+        assert_eq!(coord! {x: 12., y: 4.}, *grouped.0.last().unwrap());
+        assert_eq!(coord! {x: 0., y: 0.}, alone.0[0]);
+        // The bezier goes up to y = 3/4, the middle of its control points
+        let top = alone.0.iter().map(|c| c.y).fold(f64::MIN, f64::max);
+        assert!((top - 0.75).abs() < 1e-9);
+        assert_eq!(16 + 2, alone.0.len());
+    }
+
+    #[test]
+    fn invalid_lists() {
+        assert!(parse_vert_list("V0 0c0y1").is_err());
+        assert!(parse_vert_list("0 0").is_err());
+        assert!(parse_prim_list("L0").is_err());
+        assert!(parse_prim_list("Q0 1").is_err());
+        assert!(parse_vert_list("V0 0éé").is_err());
+        assert!(parse_vert_list("V0 0c0é1").is_err());
+        assert!(parse_prim_list("é0 1").is_err());
+        assert!(parse_prim_list("L0 1é").is_err());
+        let wide = SHAPES.replace(r#"CutIndex="0""#, r#"CutIndex="256""#);
+        assert_eq!(
+            Some(LightBurnImportError::CutIndex("256".to_string())),
+            read_lbrn2(&wide).err()
+        );
+        let missing = SHAPES.replace(
+            r#"VertID="0" PrimID="0">
+        <XForm>1 0 0 1 0 0"#,
+            r#"VertID="7" PrimID="0">
+        <XForm>1 0 0 1 0 0"#,
+        );
+        assert_eq!(
+            LightBurnImportError::MissingList(7),
+            read_lbrn2(&missing).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn round_trip_square() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.), (x: 0., y: 4.)],
+            interiors: [[(x: 1., y: 1.), (x: 2., y: 1.), (x: 2., y: 2.)]],
+        );
+
+        let layers = read_lbrn2(&polygons_to_lbrn2(std::slice::from_ref(&square))).unwrap();
+
+        assert_eq!(1, layers.len());
+        assert_eq!(Some("C00".to_string()), layers[0].name);
+        let polygons = layers[0].polygons();
+        assert_eq!(1, polygons.len());
+        assert_eq!(1, polygons[0].interiors().len());
+        assert_eq!(square.unsigned_area(), polygons[0].unsigned_area());
+    }
+
+    #[test]
+    fn read_plane_backup() {
+        let lbrn = std::fs::read_to_string("plane_backup.lbrn2").unwrap();
+
+        let layers = read_lbrn2(&lbrn).unwrap();
+
+        assert_eq!(1, layers.len());
+        assert_eq!(10, layers[0].index);
+        assert_eq!(Some("C10".to_string()), layers[0].name);
+        assert_eq!(307, layers[0].lines.len());
+        assert!(layers[0].lines.iter().all(|l| l.is_closed()));
+        // The panel, its frame holding all the other rings
+        let polygons = layers[0].polygons();
+        assert_eq!(1, polygons.len());
+        assert_eq!(306, polygons[0].interiors().len());
+        save_polygon_as_svg(&polygons[0], "test_figures/plane_backup.svg");
+        assert_valid(&polygons[0], windings_as_drawn);
+    }
+}
@@ -0,0 +1,271 @@
+use geo::orient::{Direction, Orient};
+use geo_clipper::{Clipper, EndType, JoinType};
+use geo_types::{Coord, Line, LineString, MultiPolygon, Point, Polygon};
+use rstar::primitives::GeomWithData;
+use rstar::{RTree, RTreeObject, AABB};
+use std::collections::HashMap;
+
+use crate::cutpath::Ring;
+use crate::precision::GeometryContext;
+
+/// Two edges of different rings of a polygon running along each other: the
+/// laser would cut the same line twice
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedEdge {
+    pub rings: (Ring, Ring),
+    /// Length along which the edges run together
+    pub length: f64,
+}
+
+type Edge = GeomWithData<Line, usize>;
+
+fn ring_of(index: usize) -> Ring {
+    match index {
+        0 => Ring::Exterior,
+        i => Ring::Interior(i - 1),
+    }
+}
+
+fn dot(a: Coord, b: Coord) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+/// Length along which `other` runs on `edge`, when it stays within the
+/// tolerance of its line
+fn overlap(edge: &Line, other: &Line, tolerance: f64) -> f64 {
+    let length = dot(edge.delta(), edge.delta()).sqrt();
+    if length == 0. {
+        return 0.;
+    }
+    let t = edge.delta() / length;
+    let across = |c: Coord| (t.x * (c - edge.start).y - t.y * (c - edge.start).x).abs();
+    if across(other.start) > tolerance || across(other.end) > tolerance {
+        return 0.;
+    }
+    let along = |c: Coord| dot(t, c - edge.start).clamp(0., length);
+    (along(other.end) - along(other.start)).abs()
+}
+
+/// Lists the edges of different rings that run along each other, closer than
+/// the tolerance, on more than the tolerance.  The lengths of the edges shared
+/// by the same two rings are added up.
+pub fn shared_edges(polygon: &Polygon, tolerance: f64) -> Vec<SharedEdge> {
+    let rings: Vec<&LineString> = std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .collect();
+    let edges: Vec<Edge> = rings
+        .iter()
+        .enumerate()
+        .flat_map(|(i, ring)| ring.lines().map(move |l| GeomWithData::new(l, i)))
+        .collect();
+    let tree = RTree::bulk_load(edges.clone());
+
+    let mut shared: HashMap<(usize, usize), f64> = HashMap::new();
+    for edge in edges.iter() {
+        let envelope = edge.envelope();
+        let (lower, upper) = (envelope.lower(), envelope.upper());
+        let grown = AABB::from_corners(
+            Point::new(lower.x() - tolerance, lower.y() - tolerance),
+            Point::new(upper.x() + tolerance, upper.y() + tolerance),
+        );
+        for other in tree.locate_in_envelope_intersecting(&grown) {
+            // Each pair of edges once
+            if other.data <= edge.data {
+                continue;
+            }
+            let length = overlap(edge.geom(), other.geom(), tolerance);
+            if length > tolerance {
+                *shared.entry((edge.data, other.data)).or_insert(0.) += length;
+            }
+        }
+    }
+    // In the order of the rings
+    let mut shared: Vec<((usize, usize), f64)> = shared.into_iter().collect();
+    shared.sort_by_key(|(rings, _)| *rings);
+    shared
+        .into_iter()
+        .map(|((a, b), length)| SharedEdge {
+            rings: (ring_of(a), ring_of(b)),
+            length,
+        })
+        .collect()
+}
+
+/// Finds the representative of a set, compressing the path to it
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i = i;
+    while parents[i] != root {
+        let next = parents[i];
+        parents[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// Merges the holes sharing an edge into one hole, so that every line of the
+/// panel is cut once.
+///
+/// The holes of a group are grown by the tolerance, united and shrunk back,
+/// which also closes the gaps thinner than the tolerance between them.  The
+/// merged hole takes the place of the first hole of its group, and the other
+/// holes are kept as they are.  The holes sharing an edge with the exterior
+/// are left: merging them would open the panel.
+///
+/// ```text
+///  +---+---+        +-------+
+///  |   |   |   =>   |       |
+///  +---+---+        +-------+
+/// ```
+pub fn merge_touching_holes(polygon: &Polygon, context: &GeometryContext) -> Polygon {
+    let holes = polygon.interiors();
+    let mut parents: Vec<usize> = (0..holes.len()).collect();
+    for shared in shared_edges(polygon, context.tolerance) {
+        if let (Ring::Interior(a), Ring::Interior(b)) = shared.rings {
+            let (a, b) = (find(&mut parents, a), find(&mut parents, b));
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..holes.len() {
+        groups.entry(find(&mut parents, i)).or_default().push(i);
+    }
+
+    let mut merged: Vec<LineString> = Vec::new();
+    for i in 0..holes.len() {
+        // The root of a group is its first hole
+        let Some(group) = groups.remove(&i) else {
+            continue;
+        };
+        let group: Vec<Polygon> = group
+            .into_iter()
+            .map(|j| Polygon::new(holes[j].clone(), vec![]).orient(Direction::Default))
+            .collect();
+        if group.len() == 1 {
+            merged.push(holes[i].clone());
+            continue;
+        }
+        let d = context.tolerance;
+        let miter = JoinType::Miter(2.);
+        let united = MultiPolygon::new(group)
+            .offset(d, miter, EndType::ClosedPolygon, context.scale)
+            .offset(-d, miter, EndType::ClosedPolygon, context.scale);
+        merged.extend(united.into_iter().map(|p| p.exterior().clone()));
+    }
+    Polygon::new(polygon.exterior().clone(), merged)
+}
+
+/// Unites units tiled side by side, each with its own outer strip, into
+/// pieces of material.  The edges along which two units meet disappear, with
+/// the gaps thinner than the tolerance between them, and the holes touching
+/// across the units are merged.
+pub fn merge_units(units: &[Polygon], context: &GeometryContext) -> MultiPolygon {
+    let d = context.tolerance;
+    let miter = JoinType::Miter(2.);
+    let units: Vec<Polygon> = units.iter().map(|u| u.orient(Direction::Default)).collect();
+    MultiPolygon::new(units)
+        .offset(d, miter, EndType::ClosedPolygon, context.scale)
+        .offset(-d, miter, EndType::ClosedPolygon, context.scale)
+        .into_iter()
+        .map(|p| merge_touching_holes(&p, context))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::svg::save_polygon_as_svg;
+    use crate::test_support::{assert_valid, holes_either_way, nothing};
+    use crate::test_support::{make_base, make_hexagon_config};
+    use geo::{Area, EuclideanLength, Translate};
+
+    fn square(x: f64, y: f64, side: f64) -> LineString {
+        LineString::from(vec![
+            (x, y),
+            (x + side, y),
+            (x + side, y + side),
+            (x, y + side),
+            (x, y),
+        ])
+    }
+
+    /// Length of all the cuts of a polygon
+    fn cut_length(polygon: &Polygon) -> f64 {
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(|r| r.euclidean_length())
+            .sum()
+    }
+
+    #[test]
+    fn merge_squares() {
+        // Given two squares sharing an edge, one a hair away from them, and one apart
+        //
+        //  +---+---+.+---+   +---+
+        //  |   |   |.|   |   |   |
+        //  +---+---+.+---+   +---+
+        let plate = Polygon::new(
+            square(0., 0., 10.),
+            vec![
+                square(1., 1., 1.),
+                square(2., 1., 1.),
+                square(3.0002, 1., 1.),
+                square(6., 1., 1.),
+            ],
+        );
+        let context = GeometryContext::for_geometry(&plate);
+
+        // When
+        let shared = shared_edges(&plate, context.tolerance);
+        let merged = merge_touching_holes(&plate, &context);
+
+        // Then the three first squares are one hole, cut once
+        assert_eq!(2, shared.len());
+        assert_eq!((Ring::Interior(0), Ring::Interior(1)), shared[0].rings);
+        assert!((shared[0].length - 1.).abs() < 1e-9);
+        assert_eq!(2, merged.interiors().len());
+        let hole = Polygon::new(merged.interiors()[0].clone(), vec![]);
+        assert!((hole.unsigned_area() - 3.0002).abs() < 1e-3);
+        assert_eq!(plate.interiors()[3], merged.interiors()[1]);
+        assert!((cut_length(&plate) - cut_length(&merged) - 4.).abs() < 1e-3);
+        assert!(shared_edges(&merged, context.tolerance).is_empty());
+        assert_valid(&merged, holes_either_way);
+    }
+
+    #[test]
+    fn merge_hexagon_units() {
+        // Given hexagon units tiled on the grid, each with its outer strip
+        let base = make_base();
+        let hexa = Hexagon::new_at_base(&base, &make_hexagon_config());
+        let (dx, dy) = (base.u.x(), 3. * base.u.y());
+        let grid = HoneycombGrid::new(dx, dy, 4, 5);
+        let units: Vec<Polygon> = grid
+            .nodes
+            .iter()
+            .map(|c| hexa.polygon.translate(c.x, c.y))
+            .collect();
+        let context = GeometryContext::for_geometry(&MultiPolygon::new(units.clone()));
+
+        // When
+        let merged = merge_units(&units, &context);
+
+        // Then the units are one piece, whose lines are each cut once
+        assert_eq!(1, merged.0.len());
+        let panel = &merged.0[0];
+        assert!(shared_edges(panel, context.tolerance).is_empty());
+        // The area is kept, but for the gaps thinner than the tolerance closed
+        // between the units
+        let length: f64 = units.iter().map(cut_length).sum();
+        let area: f64 = units.iter().map(|u| u.unsigned_area()).sum();
+        assert!((panel.unsigned_area() - area).abs() < length * context.tolerance);
+        assert!(cut_length(panel) < length);
+        save_polygon_as_svg(panel, "test_figures/units_merged.svg");
+        assert_valid(panel, nothing);
+    }
+}
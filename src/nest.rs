@@ -0,0 +1,335 @@
+use geo::{Area, BoundingRect, Rotate, Translate};
+use geo_types::{coord, MultiLineString, Polygon, Rect};
+use std::fmt;
+
+use crate::cutpath::CutPath;
+use crate::lightburn::save_polygons_as_lbrn2;
+use crate::svg::{write_svg, LayeredSvg};
+
+/// Tolerance when checking that a part fits in a free space
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, PartialEq)]
+pub enum NestError {
+    /// The part with the given index does not fit on an empty sheet, whatever its rotation
+    TooLarge { index: usize },
+}
+
+impl fmt::Display for NestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NestError::TooLarge { index } => write!(f, "part {} does not fit on a sheet", index),
+        }
+    }
+}
+
+/// A part placed on a sheet
+pub struct PlacedPart {
+    /// Index of the part in the list given to the nesting
+    pub index: usize,
+    /// Rotation applied to the part, in degrees
+    pub rotation: f64,
+    /// The part, rotated and moved to its place on the sheet
+    pub polygon: Polygon,
+}
+
+/// A sheet of material, with its lower left corner at the origin
+pub struct Sheet {
+    pub width: f64,
+    pub height: f64,
+    pub parts: Vec<PlacedPart>,
+    /// Free rectangles of the sheet, enlarged by the spacing
+    free: Vec<Rect>,
+}
+
+impl Sheet {
+    fn new(width: f64, height: f64, spacing: f64) -> Self {
+        Sheet {
+            width,
+            height,
+            parts: vec![],
+            free: vec![Rect::new(
+                coord! {x: 0., y: 0.},
+                coord! {x: width + spacing, y: height + spacing},
+            )],
+        }
+    }
+
+    pub fn outline(&self) -> Polygon {
+        Rect::new(
+            coord! {x: 0., y: 0.},
+            coord! {x: self.width, y: self.height},
+        )
+        .to_polygon()
+    }
+
+    pub fn polygons(&self) -> Vec<Polygon> {
+        self.parts.iter().map(|p| p.polygon.clone()).collect()
+    }
+
+    /// Ratio of the sheet covered by the parts
+    pub fn usage(&self) -> f64 {
+        let used: f64 = self.parts.iter().map(|p| p.polygon.unsigned_area()).sum();
+        used / (self.width * self.height)
+    }
+
+    /// Finds the lowest, then leftmost, free position for a box of the given size
+    fn find_position(&self, w: f64, h: f64) -> Option<(f64, f64)> {
+        self.free
+            .iter()
+            .filter(|r| w <= r.width() + EPSILON && h <= r.height() + EPSILON)
+            .map(|r| (r.min().x, r.min().y))
+            .min_by(|a, b| (a.1 + h).total_cmp(&(b.1 + h)).then(a.0.total_cmp(&b.0)))
+    }
+
+    /// Takes a box out of the free rectangles.
+    ///
+    /// Every free rectangle overlapping the box is replaced by the (up to four)
+    /// largest rectangles around the box, then the rectangles contained in
+    /// another one are dropped.
+    fn occupy(&mut self, used: Rect) {
+        let mut free: Vec<Rect> = Vec::new();
+        for r in self.free.iter() {
+            let overlaps = used.min().x < r.max().x - EPSILON
+                && used.max().x > r.min().x + EPSILON
+                && used.min().y < r.max().y - EPSILON
+                && used.max().y > r.min().y + EPSILON;
+            if !overlaps {
+                free.push(*r);
+                continue;
+            }
+            if used.min().x > r.min().x + EPSILON {
+                free.push(Rect::new(r.min(), coord! {x: used.min().x, y: r.max().y}));
+            }
+            if used.max().x < r.max().x - EPSILON {
+                free.push(Rect::new(coord! {x: used.max().x, y: r.min().y}, r.max()));
+            }
+            if used.min().y > r.min().y + EPSILON {
+                free.push(Rect::new(r.min(), coord! {x: r.max().x, y: used.min().y}));
+            }
+            if used.max().y < r.max().y - EPSILON {
+                free.push(Rect::new(coord! {x: r.min().x, y: used.max().y}, r.max()));
+            }
+        }
+        let contains = |a: &Rect, b: &Rect| {
+            a.min().x <= b.min().x + EPSILON
+                && a.min().y <= b.min().y + EPSILON
+                && a.max().x >= b.max().x - EPSILON
+                && a.max().y >= b.max().y - EPSILON
+        };
+        self.free = free
+            .iter()
+            .enumerate()
+            .filter(|(i, r)| {
+                !free.iter().enumerate().any(|(j, other)| {
+                    *i != j && contains(other, r) && (!contains(r, other) || j < *i)
+                })
+            })
+            .map(|(_, r)| *r)
+            .collect();
+    }
+}
+
+/// Nests parts on sheets of a given size.
+///
+/// The parts are placed from the largest to the smallest, each at the lowest then
+/// leftmost place where its bounding box fits, trying every allowed rotation
+/// (a max-rects, bottom-left packing).  A new sheet is started when a part does not
+/// fit on any of the previous ones.  The result only depends on the input.
+///
+/// The spacing is kept between the parts, not between the parts and the sheet edges.
+pub struct Nesting {
+    /// Width of a sheet
+    width: f64,
+    /// Height of a sheet
+    height: f64,
+    /// Minimum gap between two parts
+    spacing: f64,
+    /// Allowed rotations, in degrees
+    rotations: Vec<f64>,
+}
+
+impl Nesting {
+    pub fn new(width: f64, height: f64, spacing: f64, rotations: Vec<f64>) -> Self {
+        Nesting {
+            width,
+            height,
+            spacing,
+            rotations: if rotations.is_empty() {
+                vec![0.]
+            } else {
+                rotations
+            },
+        }
+    }
+
+    pub fn nest(&self, parts: &[Polygon]) -> Result<Vec<Sheet>, NestError> {
+        let mut order: Vec<usize> = (0..parts.len()).collect();
+        order.sort_by(|a, b| {
+            let (aa, ab) = (parts[*a].unsigned_area(), parts[*b].unsigned_area());
+            ab.total_cmp(&aa).then(a.cmp(b))
+        });
+
+        let mut sheets: Vec<Sheet> = Vec::new();
+        for index in order {
+            let rotated: Vec<(f64, Polygon, Rect)> = self
+                .rotations
+                .iter()
+                .filter_map(|angle| {
+                    let p = parts[index].rotate_around_centroid(*angle);
+                    p.bounding_rect().map(|r| (*angle, p, r))
+                })
+                .collect();
+
+            let placed = sheets
+                .iter_mut()
+                .find_map(|sheet| self.place(sheet, index, &rotated));
+            if placed.is_none() {
+                let mut sheet = Sheet::new(self.width, self.height, self.spacing);
+                self.place(&mut sheet, index, &rotated)
+                    .ok_or(NestError::TooLarge { index })?;
+                sheets.push(sheet);
+            }
+        }
+        Ok(sheets)
+    }
+
+    /// Places a part on a sheet with its best rotation, if it fits
+    fn place(
+        &self,
+        sheet: &mut Sheet,
+        index: usize,
+        rotated: &[(f64, Polygon, Rect)],
+    ) -> Option<()> {
+        let (rotation, polygon, bounds, (x, y)) = rotated
+            .iter()
+            .filter_map(|(angle, p, r)| {
+                let (w, h) = (r.width() + self.spacing, r.height() + self.spacing);
+                sheet.find_position(w, h).map(|pos| (*angle, p, r, pos))
+            })
+            .min_by(|a, b| {
+                let (ka, kb) = (a.3 .1 + a.2.height(), b.3 .1 + b.2.height());
+                ka.total_cmp(&kb).then(a.3 .0.total_cmp(&b.3 .0))
+            })?;
+
+        sheet.occupy(Rect::new(
+            coord! {x: x, y: y},
+            coord! {x: x + bounds.width() + self.spacing, y: y + bounds.height() + self.spacing},
+        ));
+        sheet.parts.push(PlacedPart {
+            index,
+            rotation,
+            polygon: polygon.translate(x - bounds.min().x, y - bounds.min().y),
+        });
+        Some(())
+    }
+}
+
+/// Writes a sheet to a layered svg file, with the outline of the sheet as construction
+pub fn save_sheet_as_svg(sheet: &Sheet, filename: &str) {
+    let outline = MultiLineString::new(vec![sheet.outline().exterior().clone()]);
+    let path = CutPath::plan(&sheet.polygons(), coord! {x: 0., y: 0.});
+    let svg = LayeredSvg::new()
+        .with_cut_path(&path)
+        .with_construction(&outline)
+        .to_string();
+    write_svg(&svg, filename);
+}
+
+/// Writes the parts of a sheet to a LightBurn file
+pub fn save_sheet_as_lbrn2(sheet: &Sheet, filename: &str) {
+    save_polygons_as_lbrn2(&sheet.polygons(), filename);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kumiko::KumikoFigure;
+    use crate::test_support::{assert_valid, holes_either_way};
+    use crate::test_support::{make_triskell_base, make_triskell_config};
+    use crate::triskell::Triskell;
+    use geo::orient::{Direction, Orient};
+    use geo_clipper::Clipper;
+
+    fn rectangle(w: f64, h: f64) -> Polygon {
+        Rect::new(coord! {x: 0., y: 0.}, coord! {x: w, y: h}).to_polygon()
+    }
+
+    fn assert_nested(sheets: &[Sheet]) {
+        for sheet in sheets {
+            let outline = sheet.outline();
+            sheet
+                .polygons()
+                .iter()
+                .for_each(|p| assert_valid(p, holes_either_way));
+            let parts: Vec<Polygon> = sheet
+                .polygons()
+                .iter()
+                .map(|p| p.orient(Direction::Default))
+                .collect();
+            for (i, a) in parts.iter().enumerate() {
+                let inside = a.intersection(&outline, 1000.0).unsigned_area();
+                assert!((inside - a.unsigned_area()).abs() < 1e-2);
+                for b in parts.iter().skip(i + 1) {
+                    assert_eq!(0., a.intersection(b, 1000.0).unsigned_area());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nest_rectangles() {
+        // Given six 4x2 boards, and a 11x4.5 sheet
+        let parts: Vec<Polygon> = (0..6).map(|_| rectangle(4., 2.)).collect();
+        let nesting = Nesting::new(11., 4.5, 0.5, vec![0., 90.]);
+
+        // When
+        let sheets = nesting.nest(&parts).unwrap();
+
+        // Then two rows of two boards, and one board turned by 90° on the right.
+        // The last board goes on a second sheet.
+        assert_eq!(2, sheets.len());
+        assert_eq!(5, sheets[0].parts.len());
+        assert_eq!(
+            1,
+            sheets[0].parts.iter().filter(|p| p.rotation == 90.).count()
+        );
+        assert_eq!(1, sheets[1].parts.len());
+        assert_nested(&sheets);
+    }
+
+    #[test]
+    fn nest_too_large() {
+        let parts = vec![rectangle(4., 2.), rectangle(12., 1.)];
+        let nesting = Nesting::new(10., 5., 0.5, vec![0., 90.]);
+
+        assert_eq!(
+            NestError::TooLarge { index: 1 },
+            nesting.nest(&parts).err().unwrap()
+        );
+    }
+
+    #[test]
+    fn nest_triskells() {
+        // Given twenty triangular triskells
+        let base = make_triskell_base();
+        let triskell = Triskell::new_at_base(&base, &make_triskell_config());
+        let parts: Vec<Polygon> = (0..20).map(|_| triskell.polygon().clone()).collect();
+        let nesting = Nesting::new(20., 15., 0.2, vec![0., 90., 180., 270.]);
+
+        // When
+        let sheets = nesting.nest(&parts).unwrap();
+        let again = nesting.nest(&parts).unwrap();
+
+        // Then
+        assert_nested(&sheets);
+        assert_eq!(sheets.len(), again.len());
+        for (a, b) in sheets.iter().zip(again.iter()) {
+            assert_eq!(a.polygons(), b.polygons());
+        }
+        let placed: usize = sheets.iter().map(|s| s.parts.len()).sum();
+        assert_eq!(20, placed);
+        save_sheet_as_svg(&sheets[0], "test_figures/nest_triskells.svg");
+        save_sheet_as_lbrn2(&sheets[0], "test_figures/nest_triskells.lbrn2");
+    }
+}
@@ -1,7 +1,14 @@
-use geo::BooleanOps;
-use geo_clipper::Clipper;
-use geo_types::{LineString, MultiLineString, Polygon};
+use geo::orient::{Direction, Orient};
+use geo::{
+    Area, BoundingRect, Closest, ClosestPoint, Contains, EuclideanDistance, EuclideanLength,
+};
+use geo_types::{Line, LineString, MultiLineString, MultiPolygon, Point, Polygon};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use rstar::{RTree, AABB};
 
+use crate::boolean::BooleanBackend;
+use crate::precision::GeometryContext;
 use crate::svg::save_polygon_as_svg;
 pub fn clip(
     interiors: &[LineString],
@@ -9,49 +16,185 @@ pub fn clip(
     clipping_line: LineString,
 ) -> Polygon {
     // Transforms the clipping line into a polygon
-    let clipping_poly: Polygon = Polygon::new(clipping_line, vec![]);
-
-    for interior in interiors {
-        let interior = MultiLineString::new(vec![interior.clone()]);
-        let res = clipping_poly.clip(&interior, false);
-        dbg!(res);
-    }
+    let clipping_poly = MultiPolygon::from(Polygon::new(clipping_line, vec![]));
+    let context = GeometryContext::for_geometry(&contour_line);
+    let backend = &context.backend;
 
     // Transforms the interiors fron an array of LineString's into a MultiLineString
     // and clips the interiors to the clipping poly
     let interiors = MultiLineString::new(interiors.to_vec());
-    let res = clipping_poly.clip(&interiors, false);
+    let res = backend.clip(&clipping_poly, &interiors, false, context.scale);
 
     // Returns a Polygon with only the clipped interior and the outer contour
     Polygon::new(contour_line, res.0)
 }
+/// What becomes of the interiors cut by the clipping line, at the edges of a panel
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BoundaryPolicy {
+    /// The parts of the interiors inside the clipping line are kept
+    #[default]
+    Keep,
+    /// The parts of the cut interiors smaller than `area`, or thinner than
+    /// `width`, are dropped
+    DropBelow { area: f64, width: f64 },
+    /// The interiors cut by the clipping line are dropped
+    DropPartial,
+    /// The vertices of the interiors closer than the distance to the clipping
+    /// line are moved onto it before clipping, so that the holes stop on the
+    /// line instead of leaving a thin strip of material or a sliver along it
+    Snap(f64),
+}
+
+/// Twice the area over the perimeter: the width of a band, half the radius of a disk
+pub fn thickness(polygon: &Polygon) -> f64 {
+    2. * polygon.unsigned_area() / polygon.exterior().euclidean_length()
+}
+
+/// Where an interior lies with respect to the clipping polygon
+#[derive(Debug, PartialEq)]
+enum Position {
+    Inside,
+    Outside,
+    Crossing,
+}
+
+/// Classifies an interior with the edges of the clipping polygon: an interior
+/// whose bounding box, grown by the margin, meets no edge is either fully
+/// inside or fully outside, as is any of its points.
+fn position(
+    interior: &LineString,
+    clipping_poly: &Polygon,
+    edges: &RTree<Line>,
+    margin: f64,
+) -> Position {
+    let Some(rect) = interior.bounding_rect() else {
+        return Position::Outside;
+    };
+    let envelope = AABB::from_corners(
+        Point::new(rect.min().x - margin, rect.min().y - margin),
+        Point::new(rect.max().x + margin, rect.max().y + margin),
+    );
+    if edges
+        .locate_in_envelope_intersecting(&envelope)
+        .next()
+        .is_some()
+    {
+        Position::Crossing
+    } else if clipping_poly.contains(&Point::from(interior.0[0])) {
+        Position::Inside
+    } else {
+        Position::Outside
+    }
+}
+
+/// Moves the vertices of a ring closer than the distance to the edges onto them
+fn snap(ring: &LineString, edges: &RTree<Line>, distance: f64) -> LineString {
+    ring.coords()
+        .map(|c| {
+            let p = Point::from(*c);
+            let nearest = edges
+                .nearest_neighbor(&p)
+                .map(|edge| edge.closest_point(&p));
+            match nearest {
+                Some(Closest::Intersection(q) | Closest::SinglePoint(q))
+                    if p.euclidean_distance(&q) < distance =>
+                {
+                    q.0
+                }
+                _ => *c,
+            }
+        })
+        .collect()
+}
+
+/// Keeps the parts of the interiors inside the clipping line, as the holes of
+/// the contour.  The interiors fully inside are kept as they are, those fully
+/// outside are dropped, and only those crossing the clipping line are
+/// clipped, in parallel with the `rayon` feature.  The holes keep the order
+/// of the interiors.
+///
+/// The precision of the clipping is chosen from the extent of the contour.
 pub fn intersect(
     interiors: &[LineString],
     contour_line: LineString,
     clipping_line: LineString,
+) -> Polygon {
+    let context = GeometryContext::for_geometry(&contour_line);
+    intersect_with(
+        interiors,
+        contour_line,
+        clipping_line,
+        &context,
+        BoundaryPolicy::Keep,
+    )
+}
+
+/// Same as [intersect], clipping with the backend and at the precision of the
+/// given context.  The interiors cut by the clipping line follow the policy.
+pub fn intersect_with(
+    interiors: &[LineString],
+    contour_line: LineString,
+    clipping_line: LineString,
+    context: &GeometryContext,
+    policy: BoundaryPolicy,
 ) -> Polygon {
     let clipping_poly: Polygon = Polygon::new(clipping_line, vec![]);
     save_polygon_as_svg(&clipping_poly, "clipper.svg");
 
     // intersection is for polygon/polygon
     let cloned = contour_line.clone();
-    let mut lines: Vec<LineString> = Vec::new();
-    for inter in interiors {
-        let poly: Polygon = Polygon::new(inter.clone(), vec![]);
-        let res = Clipper::intersection(&poly, &clipping_poly, 1000.0);
-        for p in res {
-            let ext = p.exterior();
-            lines.push(ext.clone());
+    let edges = RTree::bulk_load(clipping_poly.exterior().lines().collect());
+    let clipping = MultiPolygon::from(clipping_poly.clone());
+    let margin = match policy {
+        BoundaryPolicy::Snap(distance) => distance,
+        _ => 0.,
+    };
+    let clip = |inter: LineString| {
+        let poly = MultiPolygon::from(Polygon::new(inter, vec![]));
+        let res = context
+            .backend
+            .intersection(&poly, &clipping, context.scale);
+        res.into_iter().filter(move |p| match policy {
+            BoundaryPolicy::DropBelow { area, width } => {
+                p.unsigned_area() >= area && thickness(p) >= width
+            }
+            _ => true,
+        })
+    };
+    let clip_one = |inter: &LineString| match position(inter, &clipping_poly, &edges, margin) {
+        Position::Inside => vec![inter.clone()],
+        Position::Outside => vec![],
+        Position::Crossing => {
+            let hole = Polygon::new(inter.clone(), vec![]);
+            let parts: Vec<Polygon> = match policy {
+                BoundaryPolicy::Snap(distance) => clip(snap(inter, &edges, distance)).collect(),
+                // Near the line, but not cut
+                _ if clipping_poly.contains(&hole) => vec![hole],
+                BoundaryPolicy::DropPartial => vec![],
+                _ => clip(inter.clone()).collect(),
+            };
+            parts
+                .into_iter()
+                .map(|p| p.exterior().clone())
+                .collect::<Vec<LineString>>()
         }
-    }
+    };
+    #[cfg(feature = "rayon")]
+    let mut lines: Vec<LineString> = interiors.par_iter().flat_map_iter(clip_one).collect();
+    #[cfg(not(feature = "rayon"))]
+    let mut lines: Vec<LineString> = interiors.iter().flat_map(clip_one).collect();
     if false {
-        let poly: Polygon = Polygon::new(cloned, interiors.to_vec());
-        let res = Clipper::intersection(&poly, &clipping_poly, 1000.0);
+        let poly = MultiPolygon::from(Polygon::new(cloned, interiors.to_vec()));
+        let res = context
+            .backend
+            .intersection(&poly, &clipping, context.scale);
         for p in res {
             lines.extend_from_slice(p.interiors().into());
         }
     }
-    let figure = Polygon::new(contour_line, lines);
+    // The kept holes have the winding of the interiors, the clipped ones the
+    // winding of the backend: they are all turned the same way
+    let figure = Polygon::new(contour_line, lines).orient(Direction::Default);
     figure
 }
 
@@ -66,6 +209,8 @@ mod tests {
     }
     #[test]
     fn clip_triangles() {
+        use crate::test_support::{assert_valid, nothing};
+
         // Given
         //
         //  O                 O
@@ -89,5 +234,105 @@ mod tests {
         let clipped_coords = clipped_interior.clone().into_inner();
         let c0: Coord<f64> = Coord { x: 3.0, y: 3.0 };
         assert_eq!(c0, *clipped_coords.get(0).unwrap());
+        assert_valid(&clipped, nothing);
+    }
+
+    #[test]
+    fn classify_interiors() {
+        use super::{position, Position};
+        use crate::test_support::{assert_valid, nothing};
+        use geo::Polygon;
+        use rstar::RTree;
+
+        // Given an L shaped clipping polygon
+        //
+        //  +--+
+        //  |  |   B
+        //  |  +--+
+        //  |A  C |
+        //  +-----+
+        let clipping_poly = Polygon::new(
+            make_linestring(vec![
+                (0., 0.),
+                (4., 0.),
+                (4., 2.),
+                (2., 2.),
+                (2., 4.),
+                (0., 4.),
+            ]),
+            vec![],
+        );
+        let edges = RTree::bulk_load(clipping_poly.exterior().lines().collect());
+        let a = make_linestring(vec![(0.5, 0.5), (1.5, 0.5), (0.5, 1.5), (0.5, 0.5)]);
+        let b = make_linestring(vec![(2.5, 2.5), (3.5, 2.5), (2.5, 3.5), (2.5, 2.5)]);
+        let c = make_linestring(vec![(1.5, 1.5), (2.5, 1.5), (1.5, 2.5), (1.5, 1.5)]);
+
+        // Then
+        assert_eq!(Position::Inside, position(&a, &clipping_poly, &edges, 0.));
+        // B is in the bounding box of the polygon, but outside of it
+        assert_eq!(Position::Outside, position(&b, &clipping_poly, &edges, 0.));
+        assert_eq!(Position::Crossing, position(&c, &clipping_poly, &edges, 0.));
+
+        // and the holes inside are kept, turned clockwise
+        let contour_line = make_linestring(vec![(-1., -1.), (5., -1.), (5., 5.), (-1., 5.)]);
+        let clipped = intersect(
+            &[a.clone(), b, c],
+            contour_line,
+            clipping_poly.exterior().clone(),
+        );
+        assert_eq!(2, clipped.interiors().len());
+        let reversed: LineString = a.0.iter().rev().copied().collect();
+        assert_eq!(reversed, clipped.interiors()[0]);
+        assert_valid(&clipped, nothing);
+    }
+
+    #[test]
+    fn keeps_the_order() {
+        use crate::boolean::BooleanBackend;
+        use crate::precision::GeometryContext;
+        use crate::test_support::{assert_valid, nothing};
+        use geo::orient::{Direction, Orient};
+        use geo::winding_order::Winding;
+        use geo::{Contains, MultiPolygon, Polygon};
+
+        // Given triangles across the clipping line, which may be clipped in parallel
+        let interiors: Vec<LineString> = (0..400)
+            .map(|i| {
+                let (x, y) = ((i % 20) as f64, (i / 20) as f64);
+                make_linestring(vec![(x, y), (x + 0.8, y), (x, y + 0.8), (x, y)])
+            })
+            .collect();
+        let clipping_line =
+            make_linestring(vec![(2.2, 2.2), (17.3, 2.2), (17.3, 17.3), (2.2, 17.3)]);
+        let contour_line = make_linestring(vec![(0., 0.), (21., 0.), (21., 21.), (0., 21.)]);
+
+        // When
+        let clipped = intersect(&interiors, contour_line.clone(), clipping_line.clone());
+
+        // Then the holes are those kept or clipped one after the other
+        let context = GeometryContext::for_geometry(&clipped);
+        let clipping_poly = Polygon::new(clipping_line, vec![]);
+        let clipping = MultiPolygon::from(clipping_poly.clone());
+        let expected: Vec<LineString> = interiors
+            .iter()
+            .flat_map(|inter| {
+                let poly = Polygon::new(inter.clone(), vec![]);
+                if clipping_poly.contains(&poly) {
+                    vec![inter.clone()]
+                } else {
+                    context
+                        .backend
+                        .intersection(&poly.into(), &clipping, context.scale)
+                        .into_iter()
+                        .map(|p| p.exterior().clone())
+                        .collect()
+                }
+            })
+            .collect();
+        let expected = Polygon::new(contour_line, expected).orient(Direction::Default);
+        assert_eq!(expected.interiors(), clipped.interiors());
+        // All turning the same way, whether kept or clipped
+        assert!(clipped.interiors().iter().all(|hole| hole.is_cw()));
+        assert_valid(&clipped, nothing);
     }
 }
@@ -0,0 +1,443 @@
+use geo::BoundingRect;
+use geo_types::{Coord, LineString, Polygon, Rect};
+use std::fmt::{self, Write};
+use std::fs;
+
+/// Points in a millimeter
+const PT_PER_MM: f64 = 72. / 25.4;
+
+/// Radius of the circle of a registration mark, in mm
+const MARK_RADIUS: f64 = 3.;
+
+/// Length of the scale ruler, in mm
+const RULER_LENGTH: f64 = 100.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PaperSize {
+    A4,
+    Letter,
+}
+
+impl PaperSize {
+    /// Width and height of the paper, in mm, portrait
+    pub fn size(&self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (210., 297.),
+            PaperSize::Letter => (215.9, 279.4),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PdfError {
+    /// The margins, in mm, leave no room to print on the page
+    Margin(f64),
+    /// The overlap, in mm, is negative or does not leave the windows apart
+    Overlap(f64),
+}
+
+impl fmt::Display for PdfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PdfError::Margin(margin) => {
+                write!(f, "margins of {} mm leave no room to print", margin)
+            }
+            PdfError::Overlap(overlap) => {
+                write!(f, "an overlap of {} mm does not fit on a page", overlap)
+            }
+        }
+    }
+}
+
+/// Full size printed template of a panel, split across pages.
+///
+/// Each page prints a window of the panel inside its margins.  Two neighbour
+/// windows share a strip of `overlap` mm, with registration marks in the middle
+/// of the strip, to glue the pages together.
+///
+/// ```text
+///   +-----------+-----------+
+///   |  R2C1   + | +  R2C2   |
+///   |         | | |         |
+///   |  R1C1   + | +  R1C2   |
+///   +-----------+-----------+
+///    |||||           ^ overlap
+///    ruler
+/// ```
+pub struct PdfTemplate {
+    pub paper: PaperSize,
+    /// Millimeters in a unit of the panel, 1 for panels drawn in mm
+    pub mm_per_unit: f64,
+    /// Blank border of each page, in mm: most printers can't print there
+    pub margin: f64,
+    /// Width of the strip printed on two pages, in mm
+    pub overlap: f64,
+}
+
+impl Default for PdfTemplate {
+    fn default() -> PdfTemplate {
+        PdfTemplate {
+            paper: PaperSize::A4,
+            mm_per_unit: 1.,
+            margin: 12.,
+            overlap: 10.,
+        }
+    }
+}
+
+/// The positions of the windows along one direction, in mm from the panel's
+/// lower left corner, and the positions of the registration marks
+fn windows(length: f64, printable: f64, overlap: f64) -> (Vec<f64>, Vec<f64>) {
+    let step = printable - overlap;
+    let count = ((length - overlap) / step).ceil().max(1.) as usize;
+    let starts: Vec<f64> = (0..count).map(|k| k as f64 * step).collect();
+    let mut marks: Vec<f64> = (0..count).map(|k| k as f64 * step + overlap / 2.).collect();
+    marks.push(starts[count - 1] + printable - overlap / 2.);
+    (starts, marks)
+}
+
+fn ring_to_path(content: &mut String, ring: &LineString, to_mm: &impl Fn(Coord) -> Coord) {
+    for (i, c) in ring.coords().enumerate() {
+        let c = to_mm(*c);
+        let op = if i == 0 { "m" } else { "l" };
+        writeln!(content, "{:.3} {:.3} {}", c.x, c.y, op).unwrap();
+    }
+    writeln!(content, "h S").unwrap();
+}
+
+/// A circle with a cross, centered on a point
+fn registration_mark(content: &mut String, x: f64, y: f64) {
+    // Control point distance of a quarter circle made of a cubic Bezier
+    let k = 0.5523 * MARK_RADIUS;
+    let r = MARK_RADIUS;
+    writeln!(content, "{:.3} {:.3} m", x + r, y).unwrap();
+    for (c1, c2, end) in [
+        ((r, k), (k, r), (0., r)),
+        ((-k, r), (-r, k), (-r, 0.)),
+        ((-r, -k), (-k, -r), (0., -r)),
+        ((k, -r), (r, -k), (r, 0.)),
+    ] {
+        writeln!(
+            content,
+            "{:.3} {:.3} {:.3} {:.3} {:.3} {:.3} c",
+            x + c1.0,
+            y + c1.1,
+            x + c2.0,
+            y + c2.1,
+            x + end.0,
+            y + end.1
+        )
+        .unwrap();
+    }
+    let l = 2. * r;
+    writeln!(
+        content,
+        "S {:.3} {:.3} m {:.3} {:.3} l {:.3} {:.3} m {:.3} {:.3} l S",
+        x - l,
+        y,
+        x + l,
+        y,
+        x,
+        y - l,
+        x,
+        y + l
+    )
+    .unwrap();
+}
+
+fn text(content: &mut String, x: f64, y: f64, size: f64, text: &str) {
+    writeln!(
+        content,
+        "BT /F1 {:.1} Tf {:.3} {:.3} Td ({}) Tj ET",
+        size, x, y, text
+    )
+    .unwrap();
+}
+
+/// A ruler in the bottom margin, with a tick every centimeter
+fn ruler(content: &mut String, x: f64, y: f64) {
+    writeln!(
+        content,
+        "{:.3} {:.3} m {:.3} {:.3} l S",
+        x,
+        y,
+        x + RULER_LENGTH,
+        y
+    )
+    .unwrap();
+    for k in 0..=(RULER_LENGTH / 10.) as usize {
+        let tx = x + 10. * k as f64;
+        let h = if k % 5 == 0 { 3. } else { 1.5 };
+        writeln!(content, "{:.3} {:.3} m {:.3} {:.3} l S", tx, y, tx, y + h).unwrap();
+    }
+    text(content, x + RULER_LENGTH + 2., y, 2.5, "100 mm");
+}
+
+impl PdfTemplate {
+    /// Size of the window of the panel printed on a page, in mm
+    fn printable(&self) -> (f64, f64) {
+        let (w, h) = self.paper.size();
+        (w - 2. * self.margin, h - 2. * self.margin)
+    }
+
+    /// Checks that the windows of the pages move forward: the overlap has to be
+    /// shorter than the printable part of a page
+    pub fn check(&self) -> Result<(), PdfError> {
+        let (pw, ph) = self.printable();
+        if !(pw > 0. && ph > 0.) {
+            return Err(PdfError::Margin(self.margin));
+        }
+        if !(0. ..pw.min(ph)).contains(&self.overlap) {
+            return Err(PdfError::Overlap(self.overlap));
+        }
+        Ok(())
+    }
+
+    /// Number of columns and rows of pages needed for a panel
+    pub fn pages(&self, bounds: &Rect) -> Result<(usize, usize), PdfError> {
+        self.check()?;
+        let (pw, ph) = self.printable();
+        let columns = windows(bounds.width() * self.mm_per_unit, pw, self.overlap).0;
+        let rows = windows(bounds.height() * self.mm_per_unit, ph, self.overlap).0;
+        Ok((columns.len(), rows.len()))
+    }
+
+    /// Content streams of the pages, in mm, row by row from the bottom
+    fn page_contents(&self, polygons: &[Polygon]) -> Vec<String> {
+        let Some(bounds) = polygons
+            .iter()
+            .filter_map(|p| p.bounding_rect())
+            .reduce(|a, b| {
+                Rect::new(
+                    Coord {
+                        x: a.min().x.min(b.min().x),
+                        y: a.min().y.min(b.min().y),
+                    },
+                    Coord {
+                        x: a.max().x.max(b.max().x),
+                        y: a.max().y.max(b.max().y),
+                    },
+                )
+            })
+        else {
+            return vec![String::new()];
+        };
+        let (pw, ph) = self.printable();
+        let (columns, marks_x) = windows(bounds.width() * self.mm_per_unit, pw, self.overlap);
+        let (rows, marks_y) = windows(bounds.height() * self.mm_per_unit, ph, self.overlap);
+        let (nx, ny) = (columns.len(), rows.len());
+
+        let mut pages = Vec::new();
+        for (row, y0) in rows.iter().enumerate() {
+            for (col, x0) in columns.iter().enumerate() {
+                // From the panel to the page, in mm
+                let (dx, dy) = (self.margin - x0, self.margin - y0);
+                let to_mm = |c: Coord| Coord {
+                    x: (c.x - bounds.min().x) * self.mm_per_unit + dx,
+                    y: (c.y - bounds.min().y) * self.mm_per_unit + dy,
+                };
+                let mut content = String::new();
+                writeln!(content, "{PT_PER_MM:.5} 0 0 {PT_PER_MM:.5} 0 0 cm").unwrap();
+
+                writeln!(content, "q").unwrap();
+                writeln!(
+                    content,
+                    "{:.3} {:.3} {:.3} {:.3} re W n",
+                    self.margin, self.margin, pw, ph
+                )
+                .unwrap();
+                writeln!(content, "0.2 w 0 0 0 RG").unwrap();
+                for polygon in polygons {
+                    ring_to_path(&mut content, polygon.exterior(), &to_mm);
+                    for hole in polygon.interiors() {
+                        ring_to_path(&mut content, hole, &to_mm);
+                    }
+                }
+                writeln!(content, "0.15 w 0.8 0 0 RG").unwrap();
+                for mx in marks_x.iter() {
+                    for my in marks_y.iter() {
+                        let (x, y) = (mx + dx, my + dy);
+                        if (self.margin..=self.margin + pw).contains(&x)
+                            && (self.margin..=self.margin + ph).contains(&y)
+                        {
+                            registration_mark(&mut content, x, y);
+                        }
+                    }
+                }
+                writeln!(content, "Q").unwrap();
+
+                // The margins: the name of the page, and the ruler
+                writeln!(content, "0.2 w 0 0 0 RG").unwrap();
+                let label = format!(
+                    "R{}C{} - page {} of {}",
+                    row + 1,
+                    col + 1,
+                    row * nx + col + 1,
+                    nx * ny
+                );
+                text(
+                    &mut content,
+                    self.margin,
+                    self.margin + ph + 4.,
+                    3.5,
+                    &label,
+                );
+                ruler(&mut content, self.margin, self.margin / 2.);
+                pages.push(content);
+            }
+        }
+        pages
+    }
+
+    /// Writes a panel as a PDF document, at its real size
+    pub fn to_pdf(&self, polygons: &[Polygon]) -> Result<String, PdfError> {
+        self.check()?;
+        let contents = self.page_contents(polygons);
+        let (w, h) = self.paper.size();
+        let media_box = format!("[0 0 {:.2} {:.2}]", w * PT_PER_MM, h * PT_PER_MM);
+
+        // 1: catalog, 2: pages, 3: font, then a page and its content for each page
+        let page_ids: Vec<usize> = (0..contents.len()).map(|k| 4 + 2 * k).collect();
+        let mut objects: Vec<String> = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{id} 0 R"))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+                contents.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        for (content, id) in contents.iter().zip(page_ids.iter()) {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox {} /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                media_box,
+                id + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object).unwrap();
+        }
+        let xref = pdf.len();
+        writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1).unwrap();
+        for offset in offsets {
+            writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+        }
+        write!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .unwrap();
+        Ok(pdf)
+    }
+}
+
+pub fn save_polygons_as_pdf(
+    polygons: &[Polygon],
+    template: &PdfTemplate,
+    filename: &str,
+) -> Result<(), PdfError> {
+    let pdf = template.to_pdf(polygons)?;
+    fs::write(filename, pdf).expect("Unable to write file");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::{assert_valid, holes_either_way, make_base, make_hexagon_config};
+    use geo_types::coord;
+
+    /// Checks that each entry of the cross-reference table points to its object
+    fn assert_xref(pdf: &str) {
+        let start: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        let table = &pdf[start..];
+        for (i, entry) in table
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with("n "))
+            .enumerate()
+        {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+
+    #[test]
+    fn pages_of_large_panel() {
+        // A 40x30 cm panel, on A4 pages with 12 mm margins: windows of 186x273 mm,
+        // moving by 176x263 mm
+        let template = PdfTemplate::default();
+        let bounds = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 400., y: 300.});
+        assert_eq!(Ok((3, 2)), template.pages(&bounds));
+
+        let letter = PdfTemplate {
+            paper: PaperSize::Letter,
+            ..PdfTemplate::default()
+        };
+        let small = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 150., y: 200.});
+        assert_eq!(Ok((1, 1)), letter.pages(&small));
+    }
+
+    #[test]
+    fn windows_moving_forward() {
+        let bounds = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 400., y: 300.});
+        // A4 is 210 mm wide: 186 mm are printable within the default margins
+        for overlap in [186., 200., -1., f64::NAN] {
+            let template = PdfTemplate {
+                overlap,
+                ..PdfTemplate::default()
+            };
+            assert!(matches!(template.pages(&bounds), Err(PdfError::Overlap(_))));
+        }
+        let template = PdfTemplate {
+            margin: 105.,
+            ..PdfTemplate::default()
+        };
+        assert_eq!(Err(PdfError::Margin(105.)), template.to_pdf(&[]));
+    }
+
+    #[test]
+    fn hexagon_template() {
+        let hexa = Hexagon::new_at_base(&make_base(), &make_hexagon_config());
+        let template = PdfTemplate {
+            mm_per_unit: 40.,
+            ..PdfTemplate::default()
+        };
+
+        let pdf = template
+            .to_pdf(std::slice::from_ref(&hexa.polygon))
+            .unwrap();
+
+        let (nx, ny) = template
+            .pages(&hexa.polygon.bounding_rect().unwrap())
+            .unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.contains(&format!("/Count {}", nx * ny)));
+        assert!(pdf.contains("/MediaBox [0 0 595.28 841.89]"));
+        assert_eq!(nx * ny, pdf.matches("(100 mm) Tj").count());
+        assert_xref(&pdf);
+        assert_valid(&hexa.polygon, holes_either_way);
+        save_polygons_as_pdf(
+            std::slice::from_ref(&hexa.polygon),
+            &template,
+            "test_figures/hexa_template.pdf",
+        )
+        .unwrap();
+    }
+}
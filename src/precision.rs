@@ -0,0 +1,194 @@
+use geo::BoundingRect;
+use geo_clipper::JoinType;
+use geo_types::Rect;
+
+use crate::boolean::Backend;
+
+/// Number of steps of the Clipper grid across a panel, at least
+const RESOLUTION: f64 = 1e6;
+
+/// Number of steps of the Clipper grid in the tolerance
+const STEPS_IN_TOLERANCE: f64 = 100.;
+
+/// Tolerance of the default context
+const DEFAULT_TOLERANCE: f64 = 1e-3;
+
+/// Largest scaled coordinate.  Clipper works on 64 bits integers, and needs
+/// room for the products of coordinates when it intersects edges.
+const MAX_SCALED: f64 = 1e15;
+
+/// Precision of the geometric operations, shared by the steps that build a panel.
+///
+/// Clipper works on integers: it multiplies the coordinates by `scale` and
+/// rounds them.  The curves, and the round joins of the offsets, are replaced
+/// by lines no further than `tolerance` from them; the joins never further
+/// than the tolerance of the default context.
+///
+/// ```text
+///   1 / scale     0.000_01   step of the Clipper grid
+///   tolerance     0.001      100 steps of the grid
+/// ```
+///
+/// The boolean operations go through `backend`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeometryContext {
+    pub tolerance: f64,
+    pub scale: f64,
+    pub backend: Backend,
+}
+
+impl Default for GeometryContext {
+    /// A micrometre for both, for a unit drawn in millimetres
+    fn default() -> Self {
+        GeometryContext::new(DEFAULT_TOLERANCE, 1000.)
+    }
+}
+
+impl GeometryContext {
+    pub fn new(tolerance: f64, scale: f64) -> Self {
+        GeometryContext {
+            tolerance,
+            scale,
+            backend: Backend::default(),
+        }
+    }
+
+    /// The same precision, with another backend
+    pub fn with_backend(self, backend: Backend) -> Self {
+        GeometryContext { backend, ..self }
+    }
+
+    /// Chooses the precision from the extent of a panel: the scale is the power
+    /// of ten that puts at least a million steps across it, so that a panel
+    /// drawn in other units gives the same figure, scaled.  The scale is
+    /// lowered when the panel is so far from the origin that its coordinates
+    /// would overflow.
+    pub fn for_extent(bounds: &Rect) -> Self {
+        let size = bounds.width().max(bounds.height());
+        if !size.is_normal() {
+            return GeometryContext::default();
+        }
+        let mut scale = 10f64.powf((RESOLUTION / size).log10().ceil());
+        let far = [bounds.min(), bounds.max()]
+            .iter()
+            .map(|c| c.x.abs().max(c.y.abs()))
+            .fold(0., f64::max);
+        while far * scale > MAX_SCALED {
+            scale /= 10.;
+        }
+        GeometryContext::new(STEPS_IN_TOLERANCE / scale, scale)
+    }
+
+    /// Round joins for the offsets.  Clipper takes their tolerance on its grid.
+    /// A metre wide panel drawn in millimetres keeps its joins within a
+    /// micrometre, as with the default context, instead of its tolerance.
+    pub fn round(&self) -> JoinType {
+        JoinType::Round(self.tolerance.min(DEFAULT_TOLERANCE) * self.scale)
+    }
+
+    /// Chooses the precision from the extent of a geometry, or the default one
+    /// when it is empty
+    pub fn for_geometry(geometry: &impl BoundingRect<f64, Output = Option<Rect>>) -> Self {
+        geometry
+            .bounding_rect()
+            .map(|r| GeometryContext::for_extent(&r))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::{assert_valid, nothing};
+    use crate::test_support::{make_base, make_hexagon_config};
+    use geo::{Area, EuclideanLength, Scale};
+    use geo_types::{coord, point, Polygon};
+
+    #[test]
+    fn scale_of_extent() {
+        // A metre wide panel drawn in millimetres is rounded to a micrometre
+        let wall = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 1200., y: 800.});
+        assert_eq!(1000., GeometryContext::for_extent(&wall).scale);
+
+        // a smaller one more finely, with the tolerance of the default context
+        let mm = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 22.8, y: 26.});
+        let context = GeometryContext::for_extent(&mm);
+        assert_eq!(1e5, context.scale);
+        assert!((context.tolerance - 1e-3).abs() < 1e-15);
+
+        // The same panel in metres is not rounded to a millimetre
+        let m = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 0.0228, y: 0.026});
+        assert_eq!(1e8, GeometryContext::for_extent(&m).scale);
+
+        // Far from the origin, the scaled coordinates stay in range
+        let far = Rect::new(coord! {x: 1e12, y: 0.}, coord! {x: 1e12 + 26., y: 26.});
+        assert!(1e12 * GeometryContext::for_extent(&far).scale <= MAX_SCALED);
+
+        let point = Rect::new(coord! {x: 1., y: 1.}, coord! {x: 1., y: 1.});
+        assert_eq!(
+            GeometryContext::default(),
+            GeometryContext::for_extent(&point)
+        );
+    }
+
+    #[test]
+    fn round_joins() {
+        // The joins of a metre wide panel in millimetres are within a micrometre
+        let wall = Rect::new(coord! {x: 0., y: 0.}, coord! {x: 1200., y: 800.});
+        for context in [
+            GeometryContext::for_extent(&wall),
+            GeometryContext::default(),
+        ] {
+            let JoinType::Round(steps) = context.round() else {
+                panic!("not a round join");
+            };
+            assert!(steps / context.scale <= 1e-3);
+            assert!(steps >= 1.);
+        }
+    }
+
+    /// The hexagon panel of the split tests, drawn `factor` times larger
+    fn make_panel(factor: f64) -> Polygon {
+        let base = make_base();
+        let hexa = Hexagon::new_at_base(&base, &make_hexagon_config());
+        let unit = hexa
+            .polygon
+            .scale_around_point(factor, factor, point! {x: 0., y: 0.});
+        let dx: f64 = base.u.x() * factor;
+        let dy = 3. * base.u.y() * factor;
+        let (nx, ny) = (4, 5);
+        let mut grid = HoneycombGrid::new(dx, dy, nx, ny);
+        let inner_figure = grid.fill_with_unit(&unit);
+        // The frame crosses the hexagons
+        let width: f64 = (2 * nx - 2) as f64 * dx - 0.5 * factor;
+        let height: f64 = (ny - 1) as f64 * dy - 0.5 * factor;
+        let frame = SimpleFrame::new(
+            point! {x: 0.3 * factor, y: 0.3 * factor},
+            width,
+            height,
+            factor,
+        );
+        frame.frame(&inner_figure)
+    }
+
+    #[test]
+    fn same_panel_in_other_units() {
+        let mm = make_panel(1.);
+        assert_valid(&mm, nothing);
+        let area = mm.unsigned_area();
+        let perimeter: f64 = mm.interiors().iter().map(|h| h.euclidean_length()).sum();
+
+        // In metres, inches and kilometres, the panel has the same holes and the
+        // same area, within the rounding of the holes to a hundredth of micrometre
+        for factor in [1e-3, 1. / 25.4, 1e3] {
+            let other = make_panel(factor);
+            assert_valid(&other, nothing);
+            assert_eq!(mm.interiors().len(), other.interiors().len());
+            let other_area = other.unsigned_area() / factor / factor;
+            assert!((other_area - area).abs() < perimeter * 1e-5);
+        }
+    }
+}
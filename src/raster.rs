@@ -0,0 +1,370 @@
+use geo::BoundingRect;
+use geo_types::{Coord, LineString, Polygon};
+use std::f64::consts::PI;
+use std::fs;
+
+/// Samples per pixel, along each axis
+const SUPERSAMPLING: usize = 4;
+
+/// Color of the wood
+const WOOD: [f64; 3] = [196., 148., 92.];
+
+/// Color of the light behind the panel
+const LIGHT: [f64; 3] = [255., 236., 190.];
+
+/// Color around the panel
+const BACKGROUND: [f64; 3] = [32., 30., 28.];
+
+/// An RGB image, rows from the top
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+/// Renders a panel as if it were made of wood, lit from behind.
+///
+/// The light goes through the holes, brighter in the middle of the panel, and
+/// glows a little over the wood around them.
+pub struct Backlight {
+    /// Resolution of the image, in pixels per inch
+    pub dpi: f64,
+    /// Millimeters in a unit of the panel, 1 for panels drawn in mm
+    pub mm_per_unit: f64,
+    /// Distance the light glows over the wood, in mm
+    pub glow: f64,
+    /// Blank border around the panel, in mm
+    pub margin: f64,
+}
+
+impl Default for Backlight {
+    fn default() -> Backlight {
+        Backlight {
+            dpi: 96.,
+            mm_per_unit: 1.,
+            glow: 1.5,
+            margin: 5.,
+        }
+    }
+}
+
+/// Coverage of each pixel by the rings, with the even-odd rule
+fn coverage(
+    rings: &[&LineString],
+    width: usize,
+    height: usize,
+    to_px: impl Fn(Coord) -> Coord,
+) -> Vec<f64> {
+    let edges: Vec<(Coord, Coord)> = rings
+        .iter()
+        .flat_map(|ring| ring.lines().map(|l| (to_px(l.start), to_px(l.end))))
+        .filter(|(a, b)| a.y != b.y)
+        .collect();
+    let n = SUPERSAMPLING as f64;
+    let mut cover = vec![0.; width * height];
+    let mut crossings: Vec<f64> = Vec::new();
+    for row in 0..height {
+        for sub in 0..SUPERSAMPLING {
+            let y = row as f64 + (sub as f64 + 0.5) / n;
+            crossings.clear();
+            for (a, b) in edges.iter() {
+                if (a.y <= y) != (b.y <= y) {
+                    crossings.push(a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y));
+                }
+            }
+            crossings.sort_by(|a, b| a.total_cmp(b));
+            for span in crossings.chunks(2) {
+                if let [x0, x1] = span {
+                    // Samples of the span, at (k + 0.5) / n
+                    let first = (x0 * n - 0.5).ceil().max(0.) as usize;
+                    let last =
+                        ((x1 * n - 0.5).floor() as isize).min((width * SUPERSAMPLING) as isize - 1);
+                    for k in first as isize..=last {
+                        cover[row * width + k as usize / SUPERSAMPLING] += 1. / (n * n);
+                    }
+                }
+            }
+        }
+    }
+    cover
+}
+
+/// Box blur of a channel, along the rows then the columns
+fn blur(values: &[f64], width: usize, height: usize, radius: usize) -> Vec<f64> {
+    let pass = |values: &[f64], len: usize, count: usize, at: &dyn Fn(usize, usize) -> usize| {
+        let mut out = vec![0.; values.len()];
+        for line in 0..count {
+            for i in 0..len {
+                let (lo, hi) = (i.saturating_sub(radius), (i + radius).min(len - 1));
+                let sum: f64 = (lo..=hi).map(|j| values[at(line, j)]).sum();
+                out[at(line, i)] = sum / (2 * radius + 1) as f64;
+            }
+        }
+        out
+    };
+    let rows = pass(values, width, height, &|line, i| line * width + i);
+    pass(&rows, height, width, &|line, i| i * width + line)
+}
+
+/// Grain of the wood, between 0 and 1, at a position in mm
+fn grain(x: f64, y: f64) -> f64 {
+    let t = y + 0.8 * (x * 0.05).sin() + 0.3 * (x * 0.13 + y * 0.02).sin();
+    0.5 + 0.5 * (t * 2. * PI / 2.5).sin()
+}
+
+impl Backlight {
+    fn px_per_mm(&self) -> f64 {
+        self.dpi / 25.4
+    }
+
+    pub fn render(&self, polygon: &Polygon) -> Image {
+        let Some(bounds) = polygon.bounding_rect() else {
+            return Image {
+                width: 0,
+                height: 0,
+                pixels: vec![],
+            };
+        };
+        let scale = self.mm_per_unit * self.px_per_mm();
+        let border = self.margin * self.px_per_mm();
+        let width = (bounds.width() * scale + 2. * border).ceil() as usize;
+        let height = (bounds.height() * scale + 2. * border).ceil() as usize;
+        let to_px = |c: Coord| Coord {
+            x: (c.x - bounds.min().x) * scale + border,
+            y: (bounds.max().y - c.y) * scale + border,
+        };
+
+        let panel = coverage(&[polygon.exterior()], width, height, to_px);
+        let rings: Vec<&LineString> = std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .collect();
+        let wood = coverage(&rings, width, height, to_px);
+        let holes: Vec<f64> = panel.iter().zip(wood.iter()).map(|(p, w)| p - w).collect();
+        let radius = (self.glow * self.px_per_mm()).round().max(1.) as usize;
+        let glow = blur(&holes, width, height, radius);
+
+        let (cx, cy) = (width as f64 / 2., height as f64 / 2.);
+        let reach = (cx * cx + cy * cy).sqrt();
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in 0..height {
+            for col in 0..width {
+                let i = row * width + col;
+                let (dx, dy) = (col as f64 - cx, row as f64 - cy);
+                let light = 1. - 0.45 * (dx * dx + dy * dy) / (reach * reach);
+                let tone =
+                    0.8 + 0.2 * grain(col as f64 / self.px_per_mm(), row as f64 / self.px_per_mm());
+                let outside = 1. - panel[i];
+                let mut pixel = [0u8; 3];
+                for (k, p) in pixel.iter_mut().enumerate() {
+                    let lit = LIGHT[k] * light;
+                    let v = wood[i]
+                        * (WOOD[k] * tone + 0.6 * glow[i] * (lit - WOOD[k] * tone).max(0.))
+                        + holes[i] * lit
+                        + outside * BACKGROUND[k];
+                    *p = v.round().clamp(0., 255.) as u8;
+                }
+                pixels.push(pixel);
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for b in bytes {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+impl Image {
+    /// Encodes the image as a PNG.  The data is stored in uncompressed deflate
+    /// blocks, which any reader accepts.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((3 * self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            // No filter
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        // zlib header, then stored blocks of at most 65535 bytes
+        let mut zlib = vec![0x78, 0x01];
+        let blocks: Vec<&[u8]> = raw.chunks(65535).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            zlib.push(if i + 1 == blocks.len() { 1 } else { 0 });
+            let len = block.len() as u16;
+            zlib.extend(len.to_le_bytes());
+            zlib.extend((!len).to_le_bytes());
+            zlib.extend(*block);
+        }
+        if blocks.is_empty() {
+            zlib.extend([1, 0, 0, 0xff, 0xff]);
+        }
+        zlib.extend(adler32(&raw).to_be_bytes());
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        let mut header = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, deflate, no filter, no interlace
+        header.extend([8, 2, 0, 0, 0]);
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+pub fn save_polygon_as_png(polygon: &Polygon, backlight: &Backlight, filename: &str) {
+    fs::write(filename, backlight.render(polygon).to_png()).expect("Unable to write file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::{assert_valid, holes_either_way, make_base, make_hexagon_config};
+    use geo_types::polygon;
+
+    /// Reads back the pixels of a PNG made of stored blocks
+    fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+        let mut at = 8;
+        let (mut width, mut height, mut zlib) = (0, 0, Vec::new());
+        while at < png.len() {
+            let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+            let kind = &png[at + 4..at + 8];
+            let data = &png[at + 8..at + 8 + len];
+            let crc = u32::from_be_bytes(png[at + 8 + len..at + 12 + len].try_into().unwrap());
+            assert_eq!(crc32(&png[at + 4..at + 8 + len]), crc);
+            match kind {
+                b"IHDR" => {
+                    width = u32::from_be_bytes(data[0..4].try_into().unwrap());
+                    height = u32::from_be_bytes(data[4..8].try_into().unwrap());
+                }
+                b"IDAT" => zlib.extend(data),
+                _ => {}
+            }
+            at += 12 + len;
+        }
+        let mut raw = Vec::new();
+        let mut i = 2;
+        loop {
+            let last = zlib[i] == 1;
+            let len = u16::from_le_bytes([zlib[i + 1], zlib[i + 2]]) as usize;
+            raw.extend(&zlib[i + 5..i + 5 + len]);
+            i += 5 + len;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(adler32(&raw).to_be_bytes(), zlib[i..i + 4]);
+        (width, height, raw)
+    }
+
+    #[test]
+    fn square_with_hole() {
+        // A 4x4 cm square, with a 2x2 cm hole, at 1 pixel per mm
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.), (x: 0., y: 4.)],
+            interiors: [[(x: 1., y: 1.), (x: 3., y: 1.), (x: 3., y: 3.), (x: 1., y: 3.)]],
+        );
+        let backlight = Backlight {
+            dpi: 25.4,
+            mm_per_unit: 10.,
+            glow: 1.,
+            ..Backlight::default()
+        };
+        assert_valid(&square, holes_either_way);
+
+        let image = backlight.render(&square);
+
+        assert_eq!((50, 50), (image.width, image.height));
+        let at = |x: usize, y: usize| image.pixels[y * image.width + x];
+        // Around the panel, on the wood, in the hole
+        assert_eq!([32, 30, 28], at(2, 2));
+        let wood = at(10, 10);
+        assert!(wood[0] > wood[1] && wood[1] > wood[2] && wood[0] < 220);
+        let hole = at(25, 25);
+        assert!(hole.iter().all(|v| *v > 180));
+        // The light glows on the wood next to the hole
+        assert!(at(14, 25)[1] > at(8, 25)[1]);
+        // Half covered pixels are mixed
+        let edge = Backlight {
+            dpi: 25.4 / 0.8,
+            ..backlight
+        }
+        .render(&square);
+        assert!(edge.pixels.iter().any(|p| p[0] > 32 && p[0] < 150));
+
+        let (width, height, raw) = decode(&image.to_png());
+        assert_eq!((50, 50), (width, height));
+        assert_eq!(50 * (1 + 3 * 50), raw.len());
+        let start = 25 * (1 + 3 * 50) + 1 + 3 * 25;
+        assert_eq!(&raw[start..start + 3], &hole[..]);
+    }
+
+    #[test]
+    fn hexagon_backlight() {
+        let hexa = Hexagon::new_at_base(&make_base(), &make_hexagon_config());
+        let backlight = Backlight {
+            mm_per_unit: 10.,
+            ..Backlight::default()
+        };
+
+        let image = backlight.render(&hexa.polygon);
+
+        assert_valid(&hexa.polygon, holes_either_way);
+        let again = backlight.render(&hexa.polygon);
+        assert_eq!(image.pixels, again.pixels);
+        // Same as the reference image, but for rounding
+        let reference = fs::read("test_figures/hexa_backlight.png").unwrap();
+        let (width, height, raw) = decode(&reference);
+        assert_eq!(
+            (image.width, image.height),
+            (width as usize, height as usize)
+        );
+        let pixels = raw
+            .chunks(1 + 3 * image.width)
+            .flat_map(|row| row[1..].chunks(3));
+        for (pixel, expected) in image.pixels.iter().zip(pixels) {
+            for (v, e) in pixel.iter().zip(expected) {
+                assert!(v.abs_diff(*e) <= 2, "{:?} instead of {:?}", pixel, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,482 @@
+use geo_types::{point, Point, Polygon};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::eventail::{Eventail, EventailConfigTrait};
+use crate::goma::{Goma, GomaConfigTrait};
+use crate::hexagon::{Hexagon, HexagonConfigTrait};
+use crate::kumiko::{Base, KumikoConfig, KumikoConfigTrait, KumikoFigure};
+use crate::seigaiha::{Seigaiha, SeigaihaConfigTrait};
+use crate::triskell::{Triskell, TriskellConfigTrait};
+
+/// The shape of the base a motif is built on, u and v having the same length
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BaseGeometry {
+    /// u and v are square
+    Square,
+    /// u and v are 60° apart: the origin, and the ends of u and v, make an
+    /// equilateral triangle
+    Equilateral,
+    /// u and v are 120° apart: the sides of a rhombus made of two equilateral
+    /// triangles, a third of a hexagon
+    Rhombus,
+}
+
+impl BaseGeometry {
+    fn angle(&self) -> f64 {
+        match self {
+            BaseGeometry::Square => 90_f64.to_radians(),
+            BaseGeometry::Equilateral => 60_f64.to_radians(),
+            BaseGeometry::Rhombus => 120_f64.to_radians(),
+        }
+    }
+
+    /// A base of this shape, u at the angle the motifs are drawn with
+    /// ```text
+    ///  v              v                 v   u
+    ///  |              |  u               \ /
+    ///  O-- u          O´                  O
+    /// ```
+    pub fn base(&self, origin: Point, side: f64) -> Base {
+        let (u, v) = match self {
+            BaseGeometry::Square => (point! {x: side, y: 0.}, point! {x: 0., y: side}),
+            _ => {
+                let a = 30_f64.to_radians();
+                let u = point! {x: side * a.cos(), y: side * a.sin()};
+                let b = a + self.angle();
+                (u, point! {x: side * b.cos(), y: side * b.sin()})
+            }
+        };
+        Base::new(origin, u, v)
+    }
+
+    /// Whether the base has this shape, whatever its size and direction
+    pub fn matches(&self, base: &Base) -> bool {
+        let (u, v) = (base.u, base.v);
+        let (lu, lv) = (u.x().hypot(u.y()), v.x().hypot(v.y()));
+        if lu == 0. || (lu - lv).abs() > 1e-6 * lu {
+            return false;
+        }
+        let angle = (u.x() * v.y() - u.y() * v.x()).atan2(u.dot(v));
+        (angle - self.angle()).abs() < 1e-6
+    }
+}
+
+/// A parameter of a motif, as a CLI, a config file or a GUI shows it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+    /// The value is rounded to an integer, as a count
+    pub integer: bool,
+}
+
+impl ParamSpec {
+    pub fn new(name: &'static str, default: f64, min: f64, max: f64) -> Self {
+        ParamSpec {
+            name,
+            default,
+            min,
+            max,
+            integer: false,
+        }
+    }
+
+    pub fn count(name: &'static str, default: usize, min: usize, max: usize) -> Self {
+        ParamSpec {
+            integer: true,
+            ..ParamSpec::new(name, default as f64, min as f64, max as f64)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RegistryError {
+    UnknownMotif(String),
+    UnknownParam {
+        motif: String,
+        name: String,
+    },
+    OutOfRange {
+        name: String,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// The base does not have the shape the motif needs
+    WrongBase {
+        motif: String,
+        expected: BaseGeometry,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::UnknownMotif(name) => write!(f, "unknown motif {}", name),
+            RegistryError::UnknownParam { motif, name } => {
+                write!(f, "motif {} has no parameter {}", motif, name)
+            }
+            RegistryError::OutOfRange {
+                name,
+                value,
+                min,
+                max,
+            } => write!(f, "{} = {} is not in [{}, {}]", name, value, min, max),
+            RegistryError::WrongBase { motif, expected } => {
+                write!(f, "motif {} needs a {:?} base", motif, expected)
+            }
+        }
+    }
+}
+
+/// The values of the parameters of a motif, the missing ones at their
+/// default.  They are the config of the motif, whatever its config trait.
+pub struct Params {
+    motif: &'static str,
+    values: HashMap<&'static str, f64>,
+}
+
+impl Params {
+    /// The value of a parameter, an error if the motif does not declare it
+    pub fn get(&self, name: &str) -> Result<f64, RegistryError> {
+        self.values
+            .get(name)
+            .copied()
+            .ok_or_else(|| RegistryError::UnknownParam {
+                motif: self.motif.to_string(),
+                name: name.to_string(),
+            })
+    }
+
+    /// The value of a parameter read by a config trait, which can't fail.
+    ///
+    /// # Panics
+    ///
+    /// If the motif does not declare the parameter in its schema
+    fn value(&self, name: &str) -> f64 {
+        self.get(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl KumikoConfigTrait for Params {
+    fn width_outer(&self) -> f64 {
+        self.value("width_outer")
+    }
+
+    fn width_fine(&self) -> f64 {
+        self.value("width_fine")
+    }
+}
+
+impl EventailConfigTrait for Params {}
+
+impl TriskellConfigTrait for Params {
+    fn space(&self) -> f64 {
+        self.value("space")
+    }
+}
+
+impl GomaConfigTrait for Params {
+    fn space(&self) -> f64 {
+        self.value("space")
+    }
+}
+
+impl HexagonConfigTrait for Params {
+    fn as_eventail_config(&self) -> &dyn EventailConfigTrait {
+        self
+    }
+    fn as_triskell_config(&self) -> &dyn TriskellConfigTrait {
+        self
+    }
+}
+
+impl SeigaihaConfigTrait for Params {
+    fn rings(&self) -> usize {
+        self.value("rings") as usize
+    }
+}
+
+/// A motif built by the registry
+#[derive(Clone)]
+pub struct Figure {
+    pub polygon: Polygon,
+    pub points: Vec<Point>,
+}
+
+impl Figure {
+    fn of<KC: KumikoConfigTrait>(figure: &dyn KumikoFigure<KC>) -> Self {
+        Figure {
+            polygon: figure.polygon().clone(),
+            points: figure.points().to_vec(),
+        }
+    }
+}
+
+impl KumikoFigure<KumikoConfig> for Figure {
+    fn polygon(&self) -> &Polygon {
+        &self.polygon
+    }
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+}
+
+/// A motif, as registered: what it needs, and how to build it
+pub struct Motif {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub base: BaseGeometry,
+    pub params: Vec<ParamSpec>,
+    build: fn(&Base, &Params) -> Figure,
+}
+
+impl Motif {
+    /// A motif, with the widths shared by all the motifs, then its own parameters
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        base: BaseGeometry,
+        params: Vec<ParamSpec>,
+        build: fn(&Base, &Params) -> Figure,
+    ) -> Self {
+        let defaults = KumikoConfig::default();
+        let mut all = vec![
+            ParamSpec::new("width_fine", defaults.width_fine, 0.01, 1.),
+            ParamSpec::new("width_outer", defaults.width_outer, 0.01, 1.),
+        ];
+        all.extend(params);
+        Motif {
+            name,
+            description,
+            base,
+            params: all,
+            build,
+        }
+    }
+
+    /// Checks the values against the schema, and fills in the defaults
+    pub fn params(&self, values: &HashMap<String, f64>) -> Result<Params, RegistryError> {
+        if let Some(name) = values
+            .keys()
+            .find(|name| !self.params.iter().any(|p| p.name == name.as_str()))
+        {
+            return Err(RegistryError::UnknownParam {
+                motif: self.name.to_string(),
+                name: name.clone(),
+            });
+        }
+        let mut params = HashMap::new();
+        for spec in self.params.iter() {
+            let mut value = values.get(spec.name).copied().unwrap_or(spec.default);
+            if spec.integer {
+                value = value.round();
+            }
+            if !(spec.min..=spec.max).contains(&value) {
+                return Err(RegistryError::OutOfRange {
+                    name: spec.name.to_string(),
+                    value,
+                    min: spec.min,
+                    max: spec.max,
+                });
+            }
+            params.insert(spec.name, value);
+        }
+        Ok(Params {
+            motif: self.name,
+            values: params,
+        })
+    }
+}
+
+/// Motifs by name, to list them and build any of them from a parameter map.
+///
+/// `Registry::default()` has the motifs of this library; others can be added
+/// with `register`.
+pub struct Registry {
+    motifs: BTreeMap<&'static str, Motif>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        registry.register(Motif::new(
+            "eventail",
+            "A fan of eight rays in a rhombus",
+            BaseGeometry::Rhombus,
+            vec![],
+            |base, params| Figure::of(&Eventail::new_at_base(base, params)),
+        ));
+        registry.register(Motif::new(
+            "goma",
+            "A triangle of sesame seeds around a central triangle",
+            BaseGeometry::Equilateral,
+            vec![ParamSpec::new("space", 0.75, 0.05, 2.)],
+            |base, params| Figure::of(&Goma::new_at_base(base, params)),
+        ));
+        registry.register(Motif::new(
+            "hexagon",
+            "An eventail and three triskells in a hexagon",
+            BaseGeometry::Rhombus,
+            vec![ParamSpec::new("space", 0.75, 0.05, 2.)],
+            |base, params| Figure::of(&Hexagon::new_at_base(base, params)),
+        ));
+        registry.register(Motif::new(
+            "seigaiha",
+            "A fan of concentric rings, hidden by the fans in front of it",
+            BaseGeometry::Square,
+            vec![ParamSpec::count("rings", 4, 1, 12)],
+            |base, params| Figure::of(&Seigaiha::new_at_base(base, params)),
+        ));
+        registry.register(Motif::new(
+            "triskell",
+            "Three blades turning in an equilateral triangle",
+            BaseGeometry::Equilateral,
+            vec![ParamSpec::new("space", 0.75, 0.05, 2.)],
+            |base, params| Figure::of(&Triskell::new_at_base(base, params)),
+        ));
+        registry
+    }
+}
+
+impl Registry {
+    /// A registry without any motif
+    pub fn new() -> Self {
+        Registry {
+            motifs: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a motif, replacing the one with the same name
+    pub fn register(&mut self, motif: Motif) {
+        self.motifs.insert(motif.name, motif);
+    }
+
+    /// The motifs, sorted by name
+    pub fn motifs(&self) -> impl Iterator<Item = &Motif> {
+        self.motifs.values()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Motif> {
+        self.motifs.get(name)
+    }
+
+    /// Builds a motif on a base, from the values of some of its parameters
+    pub fn build(
+        &self,
+        name: &str,
+        base: &Base,
+        values: &HashMap<String, f64>,
+    ) -> Result<Figure, RegistryError> {
+        let motif = self
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownMotif(name.to_string()))?;
+        if !motif.base.matches(base) {
+            return Err(RegistryError::WrongBase {
+                motif: name.to_string(),
+                expected: motif.base,
+            });
+        }
+        let params = motif.params(values)?;
+        Ok((motif.build)(base, &params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{assert_valid, holes_either_way};
+    use crate::test_support::{make_base, make_hexagon_config};
+
+    #[test]
+    fn build_all() {
+        let registry = Registry::default();
+
+        let names: Vec<&str> = registry.motifs().map(|m| m.name).collect();
+        assert_eq!(
+            vec!["eventail", "goma", "hexagon", "seigaiha", "triskell"],
+            names
+        );
+        for motif in registry.motifs() {
+            let base = motif.base.base(point! {x: 0., y: 0.}, 4.);
+            let figure = registry.build(motif.name, &base, &HashMap::new()).unwrap();
+            assert!(!figure.polygon.interiors().is_empty(), "{}", motif.name);
+            assert_valid(&figure.polygon, holes_either_way);
+        }
+
+        // The hexagon of the registry is the one of its tests
+        assert!(BaseGeometry::Rhombus.matches(&make_base()));
+        let figure = registry
+            .build("hexagon", &make_base(), &HashMap::new())
+            .unwrap();
+        let hexa = Hexagon::new_at_base(&make_base(), &make_hexagon_config());
+        assert_eq!(hexa.polygon, figure.polygon);
+    }
+
+    #[test]
+    fn check_params() {
+        let registry = Registry::default();
+        let square = BaseGeometry::Square.base(point! {x: 0., y: 0.}, 2.);
+        let values = |pairs: &[(&str, f64)]| -> HashMap<String, f64> {
+            pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+        };
+
+        let few = registry
+            .build("seigaiha", &square, &values(&[("rings", 2.2)]))
+            .unwrap();
+        let many = registry
+            .build("seigaiha", &square, &values(&[("rings", 6.)]))
+            .unwrap();
+        assert!(few.polygon.interiors().len() < many.polygon.interiors().len());
+
+        let motif = registry.get("seigaiha").unwrap();
+        let params = motif.params(&HashMap::new()).unwrap();
+        assert_eq!(Ok(4.), params.get("rings"));
+        assert_eq!(
+            Err(RegistryError::UnknownParam {
+                motif: "seigaiha".to_string(),
+                name: "space".to_string()
+            }),
+            params.get("space")
+        );
+
+        assert_eq!(
+            Err(RegistryError::UnknownMotif("asanoha".to_string())),
+            registry
+                .build("asanoha", &square, &HashMap::new())
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(RegistryError::UnknownParam {
+                motif: "seigaiha".to_string(),
+                name: "space".to_string()
+            }),
+            registry
+                .build("seigaiha", &square, &values(&[("space", 1.)]))
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(RegistryError::OutOfRange {
+                name: "rings".to_string(),
+                value: 20.,
+                min: 1.,
+                max: 12.
+            }),
+            registry
+                .build("seigaiha", &square, &values(&[("rings", 20.)]))
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(RegistryError::WrongBase {
+                motif: "triskell".to_string(),
+                expected: BaseGeometry::Equilateral
+            }),
+            registry
+                .build("triskell", &square, &HashMap::new())
+                .map(|_| ())
+        );
+    }
+}
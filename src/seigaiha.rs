@@ -0,0 +1,234 @@
+use geo::Area;
+use geo_types::{Coord, LineString, MultiPolygon, Point, Polygon, Rect};
+use std::f64::consts::PI;
+
+use crate::boolean::BooleanBackend;
+use crate::curves::{CurvePath, Segment};
+use crate::grid::HoneycombGrid;
+use crate::kumiko::{Base, KumikoConfig, KumikoConfigTrait, KumikoFigure};
+use crate::operations::thickness;
+use crate::precision::GeometryContext;
+
+/// A fan of concentric rings, the unit of the seigaiha waves.
+///
+/// The fans are placed on rows half a radius apart, every row moved by a
+/// radius, and each fan is hidden by the three fans in front of it, on the
+/// rows below.  Only the visible part of the fan is kept, so that the units
+/// tile the plane.
+/// ```text
+///    .---.   .---.
+///   / .-. \ / .-. \
+///  / / .---.---. \ \
+/// | | / .-. .-. \ | |
+/// ```
+#[derive(Clone)]
+pub struct Seigaiha {
+    polygon: Polygon,
+    points: Vec<Point>,
+    radius: f64,
+}
+
+pub struct SeigaihaConfig {
+    /// Number of rings of a fan, the outer one included
+    pub rings: usize,
+    pub parent: KumikoConfig,
+}
+
+pub trait SeigaihaConfigTrait: KumikoConfigTrait {
+    fn rings(&self) -> usize;
+}
+
+impl SeigaihaConfigTrait for SeigaihaConfig {
+    fn rings(&self) -> usize {
+        self.rings
+    }
+}
+
+impl KumikoConfigTrait for SeigaihaConfig {
+    fn width_outer(&self) -> f64 {
+        self.parent.width_outer()
+    }
+
+    fn width_fine(&self) -> f64 {
+        self.parent.width_fine()
+    }
+}
+
+impl KumikoFigure<SeigaihaConfig> for Seigaiha {
+    fn polygon(&self) -> &Polygon {
+        &self.polygon
+    }
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+}
+
+/// A disk, flattened at the tolerance of the context
+fn disk(center: Coord, radius: f64, context: &GeometryContext) -> Polygon {
+    CurvePath::new(vec![Segment::Arc {
+        center,
+        radius,
+        start: 0.,
+        sweep: 2. * PI,
+    }])
+    .to_polygon(context.tolerance)
+}
+
+impl Seigaiha {
+    /// Creates the fan centered on the origin of the base, whose radius is the
+    /// length of u.  The fans in front of it are at `±u - v/2` and `-v`, v
+    /// being u turned a quarter counterclockwise.
+    ///
+    /// The rings are `width_fine` wide, and the gaps between them are the
+    /// holes.  The gaps stop half a ring away from the outer rings of the fans
+    /// in front, and the pieces thinner than half a ring are dropped, so that
+    /// the panel has no slivers.
+    ///
+    /// The circles are flattened, and the fan is cut, at the precision of the
+    /// extent of the fans.
+    pub fn new_at_base(base: &Base, config: &dyn SeigaihaConfigTrait) -> Self {
+        let center = base.origin.0;
+        let u = base.u.0;
+        let v = Coord { x: -u.y, y: u.x };
+        let radius = base.u.0.x.hypot(base.u.0.y);
+        let reach = Coord {
+            x: 2. * radius,
+            y: 2. * radius,
+        };
+        let context = &GeometryContext::for_extent(&Rect::new(center - reach, center + reach));
+        let fronts = [center + u - v / 2., center - u - v / 2., center - v];
+
+        let half = config.width_fine() / 2.;
+        let mut front = MultiPolygon::new(vec![]);
+        let mut front_rings = MultiPolygon::new(vec![]);
+        let backend = &context.backend;
+        for c in fronts {
+            front = backend.union(&front, &disk(c, radius, context).into(), context.scale);
+            front_rings = backend.union(
+                &front_rings,
+                &disk(c, radius + half, context).into(),
+                context.scale,
+            );
+        }
+        let scale = backend
+            .difference(&disk(center, radius, context).into(), &front, context.scale)
+            .into_iter()
+            .max_by(|a, b| a.unsigned_area().total_cmp(&b.unsigned_area()))
+            .unwrap();
+
+        let step = radius / config.rings() as f64;
+        let mut interiors: Vec<LineString> = vec![];
+        for k in 0..config.rings() {
+            let outer = disk(center, step * (k + 1) as f64 - half, context);
+            let gap = if k == 0 {
+                MultiPolygon::new(vec![outer])
+            } else {
+                backend.difference(
+                    &outer.into(),
+                    &disk(center, step * k as f64 + half, context).into(),
+                    context.scale,
+                )
+            };
+            for piece in backend.difference(&gap, &front_rings, context.scale) {
+                if thickness(&piece) >= half / 2. {
+                    interiors.push(piece.exterior().clone());
+                }
+            }
+        }
+
+        Seigaiha {
+            polygon: Polygon::new(scale.exterior().clone(), interiors),
+            points: vec![base.origin]
+                .into_iter()
+                .chain(fronts.iter().map(|c| Point::from(*c)))
+                .collect(),
+            radius,
+        }
+    }
+
+    /// The offset row lattice of the fans, when u is horizontal: rows half a
+    /// radius apart, fans two radii apart, every other row moved by a radius
+    pub fn grid(&self, nx: usize, ny: usize) -> HoneycombGrid {
+        HoneycombGrid::new(self.radius, self.radius / 2., nx, ny)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::svg::save_polygon_as_svg;
+    use crate::test_support::{assert_valid, holes_either_way, nothing};
+    use geo_clipper::{Clipper, EndType, JoinType};
+    use geo_types::point;
+
+    fn make_config() -> SeigaihaConfig {
+        SeigaihaConfig {
+            rings: 4,
+            parent: KumikoConfig::default(),
+        }
+    }
+
+    #[test]
+    fn fan() {
+        let base = Base::new(
+            point! {x: 0., y: 0.},
+            point! {x: 2., y: 0.},
+            point! {x: 0., y: 2.},
+        );
+        let seigaiha = Seigaiha::new_at_base(&base, &make_config());
+
+        // The visible part of a fan is a cell of the lattice
+        let scale = Polygon::new(seigaiha.polygon().exterior().clone(), vec![]);
+        assert!((scale.unsigned_area() - 4.).abs() < 1e-2);
+        assert!(seigaiha.polygon().interiors().len() >= 4);
+        for hole in seigaiha.polygon().interiors() {
+            assert!(thickness(&Polygon::new(hole.clone(), vec![])) >= 0.15 / 4.);
+        }
+        save_polygon_as_svg(seigaiha.polygon(), "test_figures/seigaiha.svg");
+        assert_valid(seigaiha.polygon(), holes_either_way);
+    }
+
+    #[test]
+    fn waves() {
+        let base = Base::new(
+            point! {x: 0., y: 0.},
+            point! {x: 2., y: 0.},
+            point! {x: 0., y: 2.},
+        );
+        let seigaiha = Seigaiha::new_at_base(&base, &make_config());
+        let (nx, ny) = (5, 12);
+        let mut grid = seigaiha.grid(nx, ny);
+
+        let holes = grid.fill_with_unit(seigaiha.polygon());
+
+        // The holes of the fans do not overlap: they are as large as their union
+        let holes: Vec<Polygon> = holes
+            .into_iter()
+            .map(|ring| Polygon::new(ring, vec![]))
+            .collect();
+        let area: f64 = holes.iter().map(|h| h.unsigned_area()).sum();
+        let union = holes
+            .iter()
+            .fold(MultiPolygon::new(vec![]), |u, h| u.union(h, 1000.0));
+        assert!((union.unsigned_area() - area).abs() < 1e-3 * area);
+        // and are a ring apart: grown by almost half a ring, they still do not overlap
+        let grown: Vec<MultiPolygon> = holes
+            .iter()
+            .map(|h| h.offset(0.07, JoinType::Round(1.), EndType::ClosedPolygon, 1000.0))
+            .collect();
+        let area: f64 = grown.iter().map(|h| h.unsigned_area()).sum();
+        let union = grown
+            .iter()
+            .fold(MultiPolygon::new(vec![]), |u, h| u.union(h, 1000.0));
+        assert!((union.unsigned_area() - area).abs() < 1e-3 * area);
+
+        // The first row has no fans in front of it
+        let frame = SimpleFrame::new(point! {x: 1., y: 2.}, 14., 8., 1.0);
+        let rings: Vec<LineString> = holes.into_iter().map(|h| h.exterior().clone()).collect();
+        let figure = frame.frame(&rings);
+        assert!(!figure.interiors().is_empty());
+        save_polygon_as_svg(&figure, "test_figures/seigaiha_plane.svg");
+        assert_valid(&figure, nothing);
+    }
+}
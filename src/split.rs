@@ -0,0 +1,557 @@
+use geo::orient::{Direction, Orient};
+use geo::{Area, BoundingRect, Centroid, Contains, Intersects};
+use geo_clipper::{Clipper, EndType, JoinType};
+use geo_types::{coord, Coord, LineString, MultiPolygon, Polygon, Rect};
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::boolean::{Backend, BooleanBackend};
+use crate::cutpath::CutPath;
+use crate::precision::GeometryContext;
+use crate::svg::{write_svg, LayeredSvg};
+use crate::validity::without_repeated_vertices;
+
+/// Joint cut along the seams between two neighbouring pieces
+#[derive(Clone, Copy, Debug)]
+pub enum Connector {
+    /// Straight seams, the pieces are simply butted together
+    None,
+    /// A round puzzle knob of the given size
+    Puzzle(f64),
+    /// A dovetail of the given size
+    Dovetail(f64),
+}
+
+impl Connector {
+    /// How far a connector sticks out of the seam
+    fn depth(&self) -> f64 {
+        match self {
+            Connector::None => 0.,
+            Connector::Puzzle(size) | Connector::Dovetail(size) => *size,
+        }
+    }
+
+    /// Shape of the connector for a seam along `t`, sticking out along `n`.
+    /// The shape overlaps the seam a little so it merges with the piece it belongs to.
+    ///
+    /// ```text
+    ///         ____          ______
+    ///        /    \         \    /
+    ///        \_  _/          \  /
+    ///  ========||====   ======\/=====  seam
+    /// ```
+    fn shape(&self, seam: Coord, t: Coord, n: Coord, context: &GeometryContext) -> Option<Polygon> {
+        let at = |along: f64, across: f64| seam + t * along + n * across;
+        match self {
+            Connector::None => None,
+            Connector::Dovetail(s) => Some(Polygon::new(
+                LineString::new(vec![
+                    at(-0.3 * s, -0.1 * s),
+                    at(0.3 * s, -0.1 * s),
+                    at(0.3 * s, 0.),
+                    at(0.5 * s, *s),
+                    at(-0.5 * s, *s),
+                    at(-0.3 * s, 0.),
+                ]),
+                vec![],
+            )),
+            Connector::Puzzle(s) => {
+                let neck = Polygon::new(
+                    LineString::new(vec![
+                        at(-0.15 * s, -0.1 * s),
+                        at(0.15 * s, -0.1 * s),
+                        at(0.15 * s, 0.6 * s),
+                        at(-0.15 * s, 0.6 * s),
+                    ]),
+                    vec![],
+                );
+                let radius = 0.35 * s;
+                let head = Polygon::new(
+                    LineString::new(
+                        (0..24)
+                            .map(|i| {
+                                let phi = i as f64 * PI / 12.;
+                                at(radius * phi.cos(), s - radius + radius * phi.sin())
+                            })
+                            .collect(),
+                    ),
+                    vec![],
+                );
+                let (neck, head) = (MultiPolygon::from(neck), MultiPolygon::from(head));
+                let shape = context.backend.union(&neck, &head, context.scale);
+                shape.0.into_iter().next()
+            }
+        }
+    }
+}
+
+/// Orientation of a seam
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Axis {
+    /// A seam at a constant x
+    Vertical,
+    /// A seam at a constant y
+    Horizontal,
+}
+
+impl Axis {
+    /// Coordinate measured across the seam
+    fn across(&self, c: Coord) -> f64 {
+        match self {
+            Axis::Vertical => c.x,
+            Axis::Horizontal => c.y,
+        }
+    }
+    /// Builds a coordinate from its position across the seam and along the seam
+    fn coord(&self, across: f64, along: f64) -> Coord {
+        match self {
+            Axis::Vertical => coord! {x: across, y: along},
+            Axis::Horizontal => coord! {x: along, y: across},
+        }
+    }
+    fn other(&self) -> Axis {
+        match self {
+            Axis::Vertical => Axis::Horizontal,
+            Axis::Horizontal => Axis::Vertical,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SplitError {
+    /// No seam can be placed after the given position without cutting a motif
+    NoSeam { position: f64 },
+    /// The connectors on both seams of a piece take all of its maximum size
+    NoRoom { limit: f64 },
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SplitError::NoSeam { position } => {
+                write!(
+                    f,
+                    "no seam fits after {:.3} without cutting a motif",
+                    position
+                )
+            }
+            SplitError::NoRoom { limit } => {
+                write!(f, "the connectors leave no room in a piece of {:.3}", limit)
+            }
+        }
+    }
+}
+
+/// One piece of a panel that was split to fit on the laser bed
+pub struct PanelPiece {
+    /// Row of the piece, counted from the bottom
+    pub row: usize,
+    /// Column of the piece, counted from the left
+    pub col: usize,
+    /// Material of the piece.  Usually a single polygon.
+    pub shape: MultiPolygon,
+}
+
+impl PanelPiece {
+    /// Position of the piece in the panel, as in "R1C2" for the first row and second column
+    pub fn label(&self) -> String {
+        format!("R{}C{}", self.row + 1, self.col + 1)
+    }
+}
+
+/// Splits a finished panel into pieces that fit on a sheet of a maximum size.
+///
+/// The seams run straight through the strips of the lattice.  When a seam would
+/// cross a hole, it goes around it, following the middle of the strips around it,
+/// so that every motif ends up whole in exactly one piece.
+///
+/// ```text
+///  +-----------+-----+----------+
+///  |  <>  <>  <|>   <|>  <>  <> |
+///  |  <>  <>  <>|  <>|  <>  <>  |
+///  +-----------+-----+----------+
+/// ```
+pub struct PanelSplitter {
+    /// Maximum width of a piece
+    max_width: f64,
+    /// Maximum height of a piece
+    max_height: f64,
+    /// Width of the strips between the holes. Seams run in their middle.
+    strip_width: f64,
+    /// Joint cut along the seams
+    connector: Connector,
+}
+
+impl PanelSplitter {
+    pub fn new(max_width: f64, max_height: f64, strip_width: f64, connector: Connector) -> Self {
+        PanelSplitter {
+            max_width,
+            max_height,
+            strip_width,
+            connector,
+        }
+    }
+
+    /// Splits the panel into pieces, returned row by row from the bottom left piece.
+    /// The precision of the cuts is chosen from the extent of the panel.
+    pub fn split(&self, panel: &Polygon) -> Result<Vec<PanelPiece>, SplitError> {
+        self.split_with(panel, Backend::default())
+    }
+
+    /// Same as [PanelSplitter::split], cutting with the given backend
+    pub fn split_with(
+        &self,
+        panel: &Polygon,
+        backend: Backend,
+    ) -> Result<Vec<PanelPiece>, SplitError> {
+        // Clipper needs the holes wound the other way round than the exterior
+        let panel = &panel.orient(Direction::Default);
+        let bounds = panel.bounding_rect().unwrap();
+        let context = &GeometryContext::for_extent(&bounds).with_backend(backend);
+        let holes: Vec<Rect> = panel
+            .interiors()
+            .iter()
+            .filter_map(|ls| ls.bounding_rect())
+            .collect();
+
+        let cols = self.seam_positions(Axis::Vertical, &bounds, &holes, self.max_width)?;
+        let rows = self.seam_positions(Axis::Horizontal, &bounds, &holes, self.max_height)?;
+
+        let columns = self.bands(Axis::Vertical, &cols, &rows, &bounds, panel, context);
+        let lines = self.bands(Axis::Horizontal, &rows, &cols, &bounds, panel, context);
+
+        let backend = context.backend;
+        let material = MultiPolygon::from(panel.clone());
+        let mut pieces: Vec<PanelPiece> = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            for (col, column) in columns.iter().enumerate() {
+                let cell = backend.intersection(column, line, context.scale);
+                // The connectors leave vertices repeated where they meet the seams
+                let shape: MultiPolygon = backend
+                    .intersection(&material, &cell, context.scale)
+                    .iter()
+                    .map(|p| without_repeated_vertices(p, context.tolerance))
+                    .collect();
+                if shape.unsigned_area() > 0. {
+                    pieces.push(PanelPiece { row, col, shape });
+                }
+            }
+        }
+        Ok(pieces)
+    }
+
+    /// Half the strip width: how far from a hole a seam goes around it
+    fn clearance(&self) -> f64 {
+        self.strip_width / 2.
+    }
+
+    /// Whether a seam at `c` has to go around a hole, and if so, whether the hole
+    /// stays before the seam
+    fn side(&self, axis: Axis, hole: &Rect, c: f64) -> Option<bool> {
+        let d = self.clearance();
+        if axis.across(hole.min()) - d < c && c < axis.across(hole.max()) + d {
+            Some(axis.across(hole.center()) < c)
+        } else {
+            None
+        }
+    }
+
+    /// Holes that a seam at `c` has to go around, with the side they stay on
+    fn crossed(&self, axis: Axis, holes: &[Rect], c: f64) -> Vec<(Rect, bool)> {
+        holes
+            .iter()
+            .filter_map(|r| self.side(axis, r, c).map(|before| (*r, before)))
+            .collect()
+    }
+
+    /// Chooses where to put the seams across the given axis, from the lowest to the highest.
+    /// Each seam is placed as far as possible, while keeping the piece before it within the limit.
+    fn seam_positions(
+        &self,
+        axis: Axis,
+        bounds: &Rect,
+        holes: &[Rect],
+        limit: f64,
+    ) -> Result<Vec<f64>, SplitError> {
+        let d = self.clearance();
+        // A piece may get a tab sticking out on both its seams, in different bands
+        if limit <= 2. * self.connector.depth() {
+            return Err(SplitError::NoRoom { limit });
+        }
+        let limit = limit - 2. * self.connector.depth();
+        let step = limit / 200.;
+        let end = axis.across(bounds.max());
+
+        let mut seams = Vec::new();
+        let mut start = axis.across(bounds.min());
+        while end - start > limit {
+            let mut c = start + limit;
+            loop {
+                let crossed = self.crossed(axis, holes, c);
+                // How far the piece before the seam reaches, and where the next one begins
+                let reach = crossed
+                    .iter()
+                    .filter(|(_, before)| *before)
+                    .map(|(r, _)| axis.across(r.max()) + d)
+                    .fold(c, f64::max);
+                let next = crossed
+                    .iter()
+                    .filter(|(_, before)| !*before)
+                    .map(|(r, _)| axis.across(r.min()) - d)
+                    .fold(c, f64::min);
+                if reach <= start + limit && next > start {
+                    seams.push(c);
+                    start = next;
+                    break;
+                }
+                c -= step;
+                if c <= start {
+                    return Err(SplitError::NoSeam { position: start });
+                }
+            }
+        }
+        Ok(seams)
+    }
+
+    /// Region of the plane lying before a seam, with the detours around the holes
+    /// and the connectors.  `cross_seams` are the seams of the other axis, between
+    /// which one connector is placed.
+    #[allow(clippy::too_many_arguments)]
+    fn region_before(
+        &self,
+        axis: Axis,
+        c: f64,
+        index: usize,
+        cross_seams: &[f64],
+        bounds: &Rect,
+        panel: &Polygon,
+        context: &GeometryContext,
+    ) -> MultiPolygon {
+        let big = bounds.width().max(bounds.height());
+        let lo = axis.across(bounds.min()) - big;
+        let other = axis.other();
+        let olo = other.across(bounds.min()) - big;
+        let ohi = other.across(bounds.max()) + big;
+        let half_plane = Rect::new(axis.coord(lo, olo), axis.coord(c, ohi)).to_polygon();
+
+        let (mut keep, mut give) = (Vec::new(), Vec::new());
+        for hole in panel.interiors() {
+            let r = hole.bounding_rect().unwrap();
+            if let Some(before) = self.side(axis, &r, c) {
+                let grown = Polygon::new(hole.clone(), vec![]).offset(
+                    self.clearance(),
+                    JoinType::Miter(2.),
+                    EndType::ClosedPolygon,
+                    context.scale,
+                );
+                if before {
+                    keep.extend(grown);
+                } else {
+                    give.extend(grown);
+                }
+            }
+        }
+        let backend = context.backend;
+        let half_plane = MultiPolygon::from(half_plane);
+        let mut region = backend.union(&half_plane, &MultiPolygon::new(keep), context.scale);
+        region = backend.difference(&region, &MultiPolygon::new(give), context.scale);
+
+        // Where three strips meet, the detours can leave small pockets on either
+        // side of the seam.  The region keeps its main part, with the pockets filled.
+        region = region
+            .into_iter()
+            .max_by(|a, b| a.unsigned_area().total_cmp(&b.unsigned_area()))
+            .map(|p| MultiPolygon::new(vec![Polygon::new(p.exterior().clone(), vec![])]))
+            .unwrap();
+
+        // One connector between two consecutive seams of the other axis
+        let mut bands = vec![other.across(bounds.min())];
+        bands.extend_from_slice(cross_seams);
+        bands.push(other.across(bounds.max()));
+        for (k, band) in bands.windows(2).enumerate() {
+            let outward = (index + k).is_multiple_of(2);
+            let tab = self.connector_in_band(axis, c, (band[0], band[1]), outward, panel, context);
+            if let Some(tab) = tab.map(MultiPolygon::from) {
+                region = if outward {
+                    backend.union(&region, &tab, context.scale)
+                } else {
+                    backend.difference(&region, &tab, context.scale)
+                };
+            }
+        }
+        region
+    }
+
+    /// Looks for a place on the seam within the band `(from, to)` where a
+    /// connector lies in solid material, starting from the middle of the band.
+    fn connector_in_band(
+        &self,
+        axis: Axis,
+        c: f64,
+        (from, to): (f64, f64),
+        outward: bool,
+        panel: &Polygon,
+        context: &GeometryContext,
+    ) -> Option<Polygon> {
+        let s = self.connector.depth();
+        if s == 0. {
+            return None;
+        }
+        let d = self.clearance();
+        let holes = panel.interiors();
+        let outline = Polygon::new(panel.exterior().clone(), vec![]);
+        let t = axis.coord(0., 1.);
+        let n = axis.coord(if outward { 1. } else { -1. }, 0.);
+        let middle = (from + to) / 2.;
+        let step = s / 4.;
+        let count = ((to - from) / 2. / step) as i64;
+        (0..=count)
+            .flat_map(|k| [middle + k as f64 * step, middle - k as f64 * step])
+            .filter(|p| *p - s > from && *p + s < to)
+            .filter_map(|p| self.connector.shape(axis.coord(c, p), t, n, context))
+            .find(|tab| {
+                // The tab and the socket both need some material around them
+                let r = tab.bounding_rect().unwrap();
+                let room = Rect::new(r.min() - coord! {x: d, y: d}, r.max() + coord! {x: d, y: d})
+                    .to_polygon();
+                outline.contains(&room)
+                    && !holes.iter().any(|h| {
+                        h.bounding_rect().unwrap().intersects(&room)
+                            && Polygon::new(h.clone(), vec![]).intersects(&room)
+                    })
+            })
+    }
+
+    /// Regions between consecutive seams of one axis
+    fn bands(
+        &self,
+        axis: Axis,
+        seams: &[f64],
+        cross_seams: &[f64],
+        bounds: &Rect,
+        panel: &Polygon,
+        context: &GeometryContext,
+    ) -> Vec<MultiPolygon> {
+        let big = bounds.width().max(bounds.height());
+        let everything = MultiPolygon::new(vec![Rect::new(
+            coord! {x: bounds.min().x - big, y: bounds.min().y - big},
+            coord! {x: bounds.max().x + big, y: bounds.max().y + big},
+        )
+        .to_polygon()]);
+
+        let mut befores: Vec<MultiPolygon> = seams
+            .iter()
+            .enumerate()
+            .map(|(i, c)| self.region_before(axis, *c, i, cross_seams, bounds, panel, context))
+            .collect();
+        befores.push(everything);
+
+        let mut previous = MultiPolygon::new(vec![]);
+        befores
+            .into_iter()
+            .map(|before| {
+                let band = context
+                    .backend
+                    .difference(&before, &previous, context.scale);
+                previous = before;
+                band
+            })
+            .collect()
+    }
+}
+
+/// Writes a piece to an svg file, with its label in the middle
+pub fn save_piece_as_svg(piece: &PanelPiece, filename: &str) {
+    let label = piece.label();
+    let bounds = piece.shape.bounding_rect().unwrap();
+    let position = piece
+        .shape
+        .centroid()
+        .map(|p| p.0)
+        .unwrap_or(bounds.center());
+
+    let path = CutPath::plan(&piece.shape.0, bounds.min());
+    let svg = LayeredSvg::new()
+        .with_cut_path(&path)
+        .with_text(&label, position, bounds.height() / 10.)
+        .to_string();
+    write_svg(&svg, filename);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::{assert_valid, nothing};
+    use crate::test_support::{make_base, make_hexagon_config};
+    use geo_types::point;
+
+    fn make_panel() -> Polygon {
+        let base = make_base();
+        let hexa = Hexagon::new_at_base(&base, &make_hexagon_config());
+        let dx: f64 = base.u.x();
+        let dy = 3. * base.u.y();
+        let (nx, ny) = (4, 5);
+        let mut grid = HoneycombGrid::new(dx, dy, nx, ny);
+        let inner_figure = grid.fill_with_unit(&hexa.polygon);
+        let width: f64 = (2 * nx - 2) as f64 * dx;
+        let height: f64 = (ny - 1) as f64 * dy;
+        let frame = SimpleFrame::new(point! {x:0.0, y:0.0}, width, height, 1.0);
+        frame.frame(&inner_figure)
+    }
+
+    #[test]
+    fn split_hexagon_plane() {
+        // Given a 22.8 x 26 panel and a 12 x 12 laser bed
+        let panel = make_panel();
+        let splitter = PanelSplitter::new(12., 12., 0.15, Connector::Dovetail(0.3));
+
+        // When
+        let pieces = splitter.split(&panel).unwrap();
+
+        // Then every piece fits, and the pieces cover the panel without cutting a hole
+        assert_eq!(6, pieces.len());
+        for piece in pieces.iter() {
+            let bounds = piece.shape.bounding_rect().unwrap();
+            assert!(bounds.width() <= 12. + 1e-6, "{} too wide", piece.label());
+            assert!(bounds.height() <= 12. + 1e-6, "{} too high", piece.label());
+            save_piece_as_svg(piece, &format!("test_figures/split_{}.svg", piece.label()));
+            piece.shape.iter().for_each(|p| assert_valid(p, nothing));
+        }
+        let area: f64 = pieces.iter().map(|p| p.shape.unsigned_area()).sum();
+        assert!((area - panel.unsigned_area()).abs() < 0.05);
+        let holes: usize = pieces
+            .iter()
+            .flat_map(|p| p.shape.iter())
+            .map(|p| p.interiors().len())
+            .sum();
+        // Clipper merges the few holes of the panel that touch each other
+        let merged = panel
+            .orient(Direction::Default)
+            .intersection(&panel.bounding_rect().unwrap().to_polygon(), 1000.0);
+        assert_eq!(merged.0[0].interiors().len(), holes);
+    }
+
+    #[test]
+    fn split_small_panel() {
+        let panel = make_panel();
+        let splitter = PanelSplitter::new(30., 30., 0.15, Connector::None);
+
+        let pieces = splitter.split(&panel).unwrap();
+
+        assert_eq!(1, pieces.len());
+        assert_eq!("R1C1", pieces[0].label());
+    }
+
+    #[test]
+    fn connectors_too_deep() {
+        let panel = make_panel();
+        let splitter = PanelSplitter::new(12., 12., 0.15, Connector::Dovetail(6.));
+
+        let result = splitter.split(&panel);
+
+        assert_eq!(Some(SplitError::NoRoom { limit: 12. }), result.err());
+    }
+}
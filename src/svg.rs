@@ -1,11 +1,29 @@
+use geo::{Contains, Translate};
 use geo_svg::Color;
-use geo_svg::{Svg, ToSvg};
-use geo_types::Polygon;
+use geo_svg::{Style, Svg, ToSvg, ToSvgStr, ViewBox};
+use geo_types::{coord, Coord, LineString, MultiLineString, Polygon};
+use std::fmt::{self, Write};
 use std::fs;
+
+use crate::curves::CurvePath;
+use crate::cutpath::{CutPath, Ring};
+use crate::operations::intersect;
+
+/// Tolerance of the lines replacing the curves, to find the viewbox
+const VIEWBOX_TOLERANCE: f64 = 1e-3;
+
 pub fn write_svg(svg: &String, filename: &str) {
     fs::write(filename, svg).expect("Unable to write file");
 }
 
+/// Escapes the characters that end a text or an attribute in xml
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 /*
 struct SvgWriter<'a> {
     filename: String,
@@ -30,14 +48,23 @@ impl<'a> SvgWriter<'a> {
 */
 
 pub fn save_polygon_as_svg(figure: &Polygon, filename: &str) {
-    let svg_figure = figure
-        .to_svg()
-        .with_stroke_width(0.01)
-        .with_stroke_color(Color::Rgb(200, 0, 0))
-        .with_fill_opacity(0.2);
+    save_polygons_as_svg(std::slice::from_ref(figure), filename);
+}
 
-    let svg = svg_figure //
-        .to_string();
+/// Writes polygons to an svg file, their rings in cut order
+pub fn save_polygons_as_svg(figures: &[Polygon], filename: &str) {
+    save_cut_path_as_svg(&CutPath::plan(figures, coord! {x: 0., y: 0.}), filename);
+}
+
+/// Writes a cut path to a layered svg file
+pub fn save_cut_path_as_svg(path: &CutPath, filename: &str) {
+    let svg = LayeredSvg::new().with_cut_path(path).to_string();
+    write_svg(&svg, filename);
+}
+
+/// Writes curve paths to a layered svg file, keeping their arcs and beziers
+pub fn save_curves_as_svg(paths: &[CurvePath], filename: &str) {
+    let svg = LayeredSvg::new().with_curves(paths).to_string();
     write_svg(&svg, filename);
 }
 
@@ -49,3 +76,441 @@ pub fn polygon_to_svg<'a>(figure: &'a Polygon) -> Svg<'a> {
         .with_fill_opacity(0.2);
     svg_figure
 }
+
+pub fn cut_path_to_svg(path: &CutPath) -> Svg<'_> {
+    path.to_svg()
+        .with_stroke_width(0.01)
+        .with_stroke_color(Color::Rgb(200, 0, 0))
+        .with_fill_opacity(0.2)
+}
+
+/// The layers of a layered svg.  Their colors are those of the first cut layers
+/// of LightBurn, which assigns a shape to a layer from its stroke color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SvgLayer {
+    /// The exteriors of the polygons
+    Frame,
+    /// The holes of the polygons
+    Holes,
+    /// Lines to help the construction, not meant to be cut
+    Construction,
+    /// Labels and other texts
+    Annotations,
+}
+
+impl SvgLayer {
+    const ALL: [SvgLayer; 4] = [
+        SvgLayer::Frame,
+        SvgLayer::Holes,
+        SvgLayer::Construction,
+        SvgLayer::Annotations,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            SvgLayer::Frame => "frame",
+            SvgLayer::Holes => "holes",
+            SvgLayer::Construction => "construction",
+            SvgLayer::Annotations => "annotations",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SvgLayer::Frame => "Frame",
+            SvgLayer::Holes => "Holes",
+            SvgLayer::Construction => "Construction",
+            SvgLayer::Annotations => "Annotations",
+        }
+    }
+
+    /// Colors of the LightBurn layers 00, 01, 03 and 05
+    pub fn color(&self) -> Color {
+        match self {
+            SvgLayer::Frame => Color::Hex(0x000000),
+            SvgLayer::Holes => Color::Hex(0x0000FF),
+            SvgLayer::Construction => Color::Hex(0x00E000),
+            SvgLayer::Annotations => Color::Hex(0xFF8000),
+        }
+    }
+
+    /// Shapes are outlined in the color of their layer, texts are filled with it
+    fn style(&self) -> Style {
+        match self {
+            SvgLayer::Annotations => Style {
+                fill: Some(self.color()),
+                ..Style::default()
+            },
+            _ => Style {
+                stroke_color: Some(self.color()),
+                stroke_width: Some(0.01),
+                fill: Some(Color::Named("none")),
+                ..Style::default()
+            },
+        }
+    }
+}
+
+/// An svg made of one `<g>` group per layer, which Inkscape shows as layers.
+///
+/// ```text
+/// <svg>
+///   <g id="frame" inkscape:groupmode="layer" inkscape:label="Frame">...</g>
+///   <g id="holes" ...>
+///   <g id="construction" ...>
+///   <g id="annotations" ...>
+/// </svg>
+/// ```
+pub struct LayeredSvg {
+    viewbox: ViewBox,
+    /// Shapes defined once, and used by the layers
+    defs: String,
+    /// Content of each layer, in the order of `SvgLayer::ALL`
+    contents: [String; 4],
+    /// Number of units defined, which numbers their ids
+    units: usize,
+}
+
+/// How the copies of a unit are written
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instancing {
+    /// The unit is defined once, and each copy is a `<use>` of it: the file stays
+    /// small, and editing the unit changes all the copies but the clipped ones
+    Use,
+    /// Each copy is written in full, for the software that does not read `<use>`
+    Flatten,
+}
+
+impl Default for LayeredSvg {
+    fn default() -> Self {
+        LayeredSvg::new()
+    }
+}
+
+impl LayeredSvg {
+    pub fn new() -> Self {
+        LayeredSvg {
+            viewbox: ViewBox::default(),
+            defs: String::new(),
+            contents: Default::default(),
+            units: 0,
+        }
+    }
+
+    /// Adds an item to a layer, in the style of the layer
+    pub fn with_item(mut self, layer: SvgLayer, item: &dyn ToSvgStr) -> Self {
+        let style = layer.style();
+        self.viewbox = self.viewbox.add(&item.viewbox(&style));
+        self.content(layer).push_str(&item.to_svg_str(&style));
+        self
+    }
+
+    fn content(&mut self, layer: SvgLayer) -> &mut String {
+        let index = SvgLayer::ALL.iter().position(|l| *l == layer).unwrap();
+        &mut self.contents[index]
+    }
+
+    /// Adds the cuts of the exteriors to the frame layer, and those of the holes
+    /// to the holes layer, keeping the cut order in each layer
+    pub fn with_cut_path(self, path: &CutPath) -> Self {
+        let (exteriors, holes): (Vec<_>, Vec<_>) = path
+            .cuts
+            .iter()
+            .cloned()
+            .partition(|cut| cut.ring == Ring::Exterior);
+        let mut svg = self;
+        for (layer, cuts) in [(SvgLayer::Holes, holes), (SvgLayer::Frame, exteriors)] {
+            if !cuts.is_empty() {
+                svg = svg.with_item(layer, &CutPath { cuts });
+            }
+        }
+        svg
+    }
+
+    /// Adds to the holes layer the holes of a unit, copied at each node, and
+    /// clipped to a contour if given, as `HoneycombGrid::fill_with_unit` and
+    /// `Frame::frame` do.
+    ///
+    /// With `Instancing::Use`, the copies inside the contour use the unit; those
+    /// crossing it are written clipped, as with `Instancing::Flatten`, so that
+    /// both write the same cuts.  Each call defines its own unit.
+    ///
+    /// ```text
+    /// <defs>
+    ///   <g id="unit-0">...</g>
+    /// </defs>
+    /// <g id="holes" ...>
+    ///   <use xlink:href="#unit-0" transform="translate(x y)"/>
+    ///   ...
+    ///   <path .../>
+    /// ```
+    pub fn with_instances(
+        mut self,
+        unit: &Polygon,
+        nodes: &[Coord],
+        clip: Option<&LineString>,
+        instancing: Instancing,
+    ) -> Self {
+        let (used, clipped): (Vec<&Coord>, Vec<&Coord>) = match (instancing, clip) {
+            (Instancing::Flatten, _) => (vec![], nodes.iter().collect()),
+            (Instancing::Use, None) => (nodes.iter().collect(), vec![]),
+            (Instancing::Use, Some(clip)) => {
+                let contour = Polygon::new(clip.clone(), vec![]);
+                nodes.iter().partition(|node| {
+                    let copy = unit.translate(node.x, node.y);
+                    copy.interiors()
+                        .iter()
+                        .all(|hole| contour.contains(&Polygon::new(hole.clone(), vec![])))
+                })
+            }
+        };
+
+        if !used.is_empty() {
+            let style = SvgLayer::Holes.style();
+            let id = format!("unit-{}", self.units);
+            self.units += 1;
+            let holes: Vec<Polygon> = unit
+                .interiors()
+                .iter()
+                .map(|hole| Polygon::new(hole.clone(), vec![]))
+                .collect();
+            write!(
+                self.defs,
+                r#"<g id="{}">{}</g>"#,
+                id,
+                holes.to_svg_str(&style)
+            )
+            .unwrap();
+            let mut uses = String::new();
+            for node in used {
+                write!(
+                    uses,
+                    r##"<use xlink:href="#{}" transform="translate({} {})"/>"##,
+                    id, node.x, node.y
+                )
+                .unwrap();
+                let viewbox = unit.translate(node.x, node.y).viewbox(&style);
+                self.viewbox = self.viewbox.add(&viewbox);
+            }
+            self.content(SvgLayer::Holes).push_str(&uses);
+        }
+
+        if !clipped.is_empty() {
+            let copies: Vec<LineString> = clipped
+                .iter()
+                .flat_map(|node| unit.translate(node.x, node.y).interiors().to_vec())
+                .collect();
+            let rings = match clip {
+                Some(clip) => intersect(&copies, clip.clone(), clip.clone())
+                    .interiors()
+                    .to_vec(),
+                None => copies,
+            };
+            let copies: Vec<Polygon> = rings
+                .into_iter()
+                .map(|ring| Polygon::new(ring, vec![]))
+                .collect();
+            self = self.with_item(SvgLayer::Holes, &copies);
+        }
+        self
+    }
+
+    pub fn with_curve(mut self, layer: SvgLayer, path: &CurvePath) -> Self {
+        let style = layer.style();
+        let outline = path.to_line_string(VIEWBOX_TOLERANCE);
+        self.viewbox = self.viewbox.add(&outline.viewbox(&style));
+        write!(
+            self.content(layer),
+            r#"<path d="{}"{}/>"#,
+            path.svg_path_data(),
+            style
+        )
+        .unwrap();
+        self
+    }
+
+    /// Adds the closed paths turning clockwise to the holes layer, and the
+    /// other paths to the frame layer
+    pub fn with_curves(self, paths: &[CurvePath]) -> Self {
+        paths.iter().fold(self, |svg, path| {
+            let layer = if path.is_hole() {
+                SvgLayer::Holes
+            } else {
+                SvgLayer::Frame
+            };
+            svg.with_curve(layer, path)
+        })
+    }
+
+    pub fn with_construction(self, lines: &MultiLineString) -> Self {
+        self.with_item(SvgLayer::Construction, lines)
+    }
+
+    /// Adds a text to the annotations layer, filled with the color of the layer
+    pub fn with_text(mut self, text: &str, position: Coord, font_size: f64) -> Self {
+        let color = SvgLayer::Annotations.color();
+        self.content(SvgLayer::Annotations).push_str(&format!(
+            r#"<text font-size="{}" x="{}" y="{}" fill="{}">{}</text>"#,
+            font_size,
+            position.x,
+            position.y,
+            color,
+            escape_xml(text)
+        ));
+        self
+    }
+}
+
+impl fmt::Display for LayeredSvg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" preserveAspectRatio="xMidYMid meet" viewBox="{} {} {} {}">"#,
+            self.viewbox.min_x(),
+            self.viewbox.min_y(),
+            self.viewbox.width(),
+            self.viewbox.height()
+        )?;
+        if !self.defs.is_empty() {
+            write!(f, "<defs>{}</defs>", self.defs)?;
+        }
+        for (layer, content) in SvgLayer::ALL.iter().zip(self.contents.iter()) {
+            write!(
+                f,
+                r#"<g id="{}" inkscape:groupmode="layer" inkscape:label="{}">{}</g>"#,
+                layer.id(),
+                layer.label(),
+                content
+            )?;
+        }
+        write!(f, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curves::{CurvesLR, Segment};
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::test_support::make_drop;
+    use crate::test_support::{assert_valid, holes_either_way, nothing};
+    use crate::test_support::{make_base, make_hexagon_config};
+    use geo_types::{line_string, point, polygon};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn layered_square() {
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.), (x: 0., y: 4.)],
+            interiors: [[(x: 1., y: 1.), (x: 2., y: 1.), (x: 2., y: 2.)]],
+        );
+        let diagonal = MultiLineString::new(vec![line_string![(x: 0., y: 0.), (x: 4., y: 4.)]]);
+        assert_valid(&square, holes_either_way);
+
+        let svg = LayeredSvg::new()
+            .with_cut_path(&CutPath::from_polygon(&square))
+            .with_construction(&diagonal)
+            .with_text("A & <B>", coord! {x: 3., y: 3.}, 0.5)
+            .to_string();
+
+        let groups: Vec<&str> = svg.split("<g ").skip(1).collect();
+        assert_eq!(4, groups.len());
+        for (group, layer) in groups.iter().zip(SvgLayer::ALL.iter()) {
+            assert!(group.starts_with(&format!(
+                r#"id="{}" inkscape:groupmode="layer" inkscape:label="{}">"#,
+                layer.id(),
+                layer.label()
+            )));
+            assert_eq!(
+                1,
+                group.matches("<path").count() + group.matches("<text").count()
+            );
+        }
+        assert!(groups[0].contains(r##"stroke="#000000""##));
+        assert!(groups[0].contains("M 0.0 0.0"));
+        assert!(groups[1].contains(r##"stroke="#0000FF""##));
+        assert!(groups[1].contains("M 1.0 1.0"));
+        assert!(groups[3].contains(r##"fill="#FF8000""##));
+        assert!(groups[3].contains(">A &amp; &lt;B&gt;</text>"));
+        assert!(svg.starts_with("<svg") && svg.contains(r#"viewBox="-0.01 -0.01 4.02"#));
+    }
+
+    #[test]
+    fn instanced_plane() {
+        let base = make_base();
+        let hexa = Hexagon::new_at_base(&base, &make_hexagon_config());
+        let (dx, dy) = (base.u.x(), 3. * base.u.y());
+        let (nx, ny) = (4, 5);
+        let mut grid = HoneycombGrid::new(dx, dy, nx, ny);
+        let width: f64 = (2 * nx - 2) as f64 * dx;
+        let height: f64 = (ny - 1) as f64 * dy;
+        let frame = SimpleFrame::new(point! {x: 0., y: 0.}, width, height, 1.0);
+        let figure = frame.frame(&grid.fill_with_unit(&hexa.polygon));
+        let inner = frame.inner_contour();
+        let outer = frame.outer_contour();
+        assert_valid(&figure, nothing);
+
+        let instanced = LayeredSvg::new()
+            .with_item(SvgLayer::Frame, &Polygon::new(outer.clone(), vec![]))
+            .with_instances(&hexa.polygon, &grid.nodes, Some(&inner), Instancing::Use)
+            .to_string();
+        let flat = LayeredSvg::new()
+            .with_item(SvgLayer::Frame, &Polygon::new(outer, vec![]))
+            .with_instances(
+                &hexa.polygon,
+                &grid.nodes,
+                Some(&inner),
+                Instancing::Flatten,
+            )
+            .to_string();
+
+        // The unit is written once, and used at each node inside the frame
+        let holes = hexa.polygon.interiors().len();
+        let uses = instanced.matches("<use ").count();
+        assert_eq!(1, instanced.matches(r#"<g id="unit-0">"#).count());
+        assert!(uses > 0 && uses < grid.nodes.len());
+        assert!(!instanced.contains("clipPath"));
+        // The other copies are clipped, as when flattened
+        assert!(!flat.contains("<use") && !flat.contains("<defs>"));
+        assert_eq!(figure.interiors().len() + 1, flat.matches("<path").count());
+        let clipped = instanced.matches("<path").count() - holes - 1;
+        assert_eq!(figure.interiors().len(), uses * holes + clipped);
+        assert!(instanced.len() < flat.len() / 2);
+        // A second unit has its own id
+        let twice = LayeredSvg::new()
+            .with_instances(&hexa.polygon, &grid.nodes, None, Instancing::Use)
+            .with_instances(&hexa.polygon, &grid.nodes, None, Instancing::Use)
+            .to_string();
+        assert!(twice.contains(r#"<g id="unit-0">"#) && twice.contains(r#"<g id="unit-1">"#));
+        assert_eq!(grid.nodes.len(), twice.matches("#unit-1").count());
+        write_svg(&instanced, "test_figures/plane_instanced.svg");
+        write_svg(&flat, "test_figures/plane_flat.svg");
+    }
+
+    #[test]
+    fn curves_kept() {
+        let drop = make_drop();
+        let lr = CurvesLR::new(&drop, 0.2, 1e-4);
+        let hole = CurvePath::new(vec![Segment::Arc {
+            center: coord! {x: 0., y: -0.5},
+            radius: 0.3,
+            start: 0.,
+            sweep: -2. * PI,
+        }]);
+
+        let svg = LayeredSvg::new()
+            .with_curves(&[lr.r.clone(), hole])
+            .to_string();
+
+        let groups: Vec<&str> = svg.split("<g ").skip(1).collect();
+        assert_eq!(1, groups[0].matches("<path").count());
+        assert!(groups[0].contains(r#"<path d="M1.1 0A1.1 1.1 0 0 1 "#));
+        assert!(groups[0].contains('C'));
+        // The full circle is written as two half circles, clockwise
+        assert_eq!(2, groups[1].matches("A0.3 0.3 0 0 0 ").count());
+        assert!(svg.contains(r#"viewBox="-1.11 "#));
+        save_curves_as_svg(&[drop, lr.l, lr.r], "test_figures/drop.svg");
+    }
+}
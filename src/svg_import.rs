@@ -0,0 +1,653 @@
+use geo::{AffineOps, AffineTransform};
+use geo_types::{coord, Coord, LineString, Polygon};
+use minidom::Element;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// Number of segments a curve is replaced with
+const CURVE_SEGMENTS: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum SvgImportError {
+    /// The document is not well formed xml
+    Xml(String),
+    /// The path data of an element can't be read
+    PathData(String),
+    /// No element has the id of the unit cell boundary
+    NoBoundary(String),
+    /// A length has a relative unit, such as `%` or `em`, or none can be read
+    Length(String),
+    /// A `<use>` refers to no element, or to one of its ancestors
+    Reference(String),
+}
+
+impl fmt::Display for SvgImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SvgImportError::Xml(e) => write!(f, "invalid svg: {}", e),
+            SvgImportError::PathData(d) => write!(f, "invalid path data: {}", d),
+            SvgImportError::NoBoundary(id) => write!(f, "no element with id {}", id),
+            SvgImportError::Length(l) => write!(f, "invalid length: {}", l),
+            SvgImportError::Reference(r) => write!(f, "invalid reference: {}", r),
+        }
+    }
+}
+
+/// Reads a unit motif drawn in an svg editor.
+///
+/// The `<path>`, `<polygon>` and `<rect>` elements are read with the transforms
+/// of their groups, every closed sub-path making a ring; a `<use>` reads the
+/// element it refers to, moved by its `x`, `y` and transform.  The rings of the
+/// element with the boundary id, or inside a group with this id, make the
+/// exterior of the unit; all the others make its holes.
+///
+/// The coordinates are the user units of the document, with y going down, as
+/// written by the svg functions of this library; lengths in absolute units, such
+/// as `10mm`, are converted to user units at 96 per inch.  Curves are replaced
+/// with lines.
+pub struct SvgImport {
+    boundary_id: String,
+}
+
+impl SvgImport {
+    pub fn new(boundary_id: &str) -> Self {
+        SvgImport {
+            boundary_id: boundary_id.to_string(),
+        }
+    }
+
+    pub fn read_unit(&self, svg: &str) -> Result<Polygon, SvgImportError> {
+        let root: Element = svg
+            .parse()
+            .map_err(|e: minidom::Error| SvgImportError::Xml(e.to_string()))?;
+        let mut document = Document {
+            root: &root,
+            boundary: Vec::new(),
+            holes: Vec::new(),
+            expanding: Vec::new(),
+        };
+        self.read_element(&root, AffineTransform::identity(), false, &mut document)?;
+        // The largest ring of the boundary, if it has several
+        let exterior = document
+            .boundary
+            .into_iter()
+            .max_by(|a, b| ring_area(a).total_cmp(&ring_area(b)))
+            .ok_or_else(|| SvgImportError::NoBoundary(self.boundary_id.clone()))?;
+        Ok(Polygon::new(exterior, document.holes))
+    }
+
+    fn read_element<'a>(
+        &self,
+        element: &'a Element,
+        parent: AffineTransform,
+        in_boundary: bool,
+        document: &mut Document<'a>,
+    ) -> Result<(), SvgImportError> {
+        // Definitions are only drawn through a <use>
+        if matches!(element.name(), "defs" | "clipPath" | "mask" | "symbol") {
+            return Ok(());
+        }
+        let transform = match element.attr("transform") {
+            Some(t) => parent.compose(&parse_transform(t)?),
+            None => parent,
+        };
+        let in_boundary = in_boundary || element.attr("id") == Some(self.boundary_id.as_str());
+
+        let rings = match element.name() {
+            "path" => parse_path(element.attr("d").unwrap_or(""))?,
+            "polygon" => {
+                let numbers = parse_numbers(element.attr("points").unwrap_or(""))?;
+                let coords: Vec<Coord> = numbers
+                    .chunks_exact(2)
+                    .map(|xy| coord! {x: xy[0], y: xy[1]})
+                    .collect();
+                vec![LineString::new(coords)]
+            }
+            "rect" => {
+                let (x, y) = (length(element, "x")?, length(element, "y")?);
+                let (w, h) = (length(element, "width")?, length(element, "height")?);
+                vec![LineString::from(vec![
+                    (x, y),
+                    (x + w, y),
+                    (x + w, y + h),
+                    (x, y + h),
+                ])]
+            }
+            "use" => {
+                self.read_use(element, transform, in_boundary, document)?;
+                vec![]
+            }
+            _ => vec![],
+        };
+        for mut ring in rings {
+            if ring.0.len() < 3 {
+                continue;
+            }
+            ring.close();
+            let ring = ring.affine_transform(&transform);
+            if in_boundary {
+                document.boundary.push(ring);
+            } else {
+                document.holes.push(ring);
+            }
+        }
+
+        for child in element.children() {
+            self.read_element(child, transform, in_boundary, document)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the element a `<use>` refers to, or the content of a `<symbol>`
+    fn read_use<'a>(
+        &self,
+        element: &'a Element,
+        transform: AffineTransform,
+        in_boundary: bool,
+        document: &mut Document<'a>,
+    ) -> Result<(), SvgImportError> {
+        let href = element
+            .attr("href")
+            .or_else(|| element.attr("xlink:href"))
+            .unwrap_or("");
+        let id = href
+            .strip_prefix('#')
+            .ok_or_else(|| SvgImportError::Reference(href.to_string()))?;
+        let referred = find_by_id(document.root, id)
+            .filter(|_| !document.expanding.contains(&id))
+            .ok_or_else(|| SvgImportError::Reference(href.to_string()))?;
+        let (x, y) = (length(element, "x")?, length(element, "y")?);
+        let transform = transform.compose(&AffineTransform::translate(x, y));
+
+        document.expanding.push(id);
+        let read = if referred.name() == "symbol" {
+            let in_boundary = in_boundary || id == self.boundary_id;
+            referred
+                .children()
+                .try_for_each(|child| self.read_element(child, transform, in_boundary, document))
+        } else {
+            self.read_element(referred, transform, in_boundary, document)
+        };
+        document.expanding.pop();
+        read
+    }
+}
+
+/// The document being read, and the rings read so far
+struct Document<'a> {
+    root: &'a Element,
+    boundary: Vec<LineString>,
+    holes: Vec<LineString>,
+    /// Ids of the elements whose `<use>` is being read, to stop on a cycle
+    expanding: Vec<&'a str>,
+}
+
+fn find_by_id<'a>(element: &'a Element, id: &str) -> Option<&'a Element> {
+    if element.attr("id") == Some(id) {
+        return Some(element);
+    }
+    element.children().find_map(|child| find_by_id(child, id))
+}
+
+/// Reads a length attribute in user units, 0 when it is missing
+fn length(element: &Element, name: &str) -> Result<f64, SvgImportError> {
+    let Some(value) = element.attr(name) else {
+        return Ok(0.);
+    };
+    let value = value.trim();
+    let error = || SvgImportError::Length(value.to_string());
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic() || c == '%')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    // Exponents are part of the number
+    let (number, unit) = match unit.strip_prefix(['e', 'E']) {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+') => {
+            let end = rest
+                .find(|c: char| c.is_ascii_alphabetic() || c == '%')
+                .unwrap_or(rest.len());
+            value.split_at(split + 1 + end)
+        }
+        _ => (number, unit),
+    };
+    let per_unit = match unit {
+        "" | "px" => 1.,
+        "in" => 96.,
+        "cm" => 96. / 2.54,
+        "mm" => 96. / 25.4,
+        "pt" => 96. / 72.,
+        "pc" => 16.,
+        _ => return Err(error()),
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .map(|n| n * per_unit)
+        .map_err(|_| error())
+}
+
+pub(crate) fn ring_area(ring: &LineString) -> f64 {
+    use geo::Area;
+    Polygon::new(ring.clone(), vec![]).unsigned_area()
+}
+
+/// Reads the numbers of a list separated by spaces or commas
+fn parse_numbers(s: &str) -> Result<Vec<f64>, SvgImportError> {
+    let mut reader = PathReader::new(s);
+    let mut numbers = Vec::new();
+    while let Some(n) = reader.number() {
+        numbers.push(n);
+    }
+    reader.skip_separators();
+    if reader.at_end() {
+        Ok(numbers)
+    } else {
+        Err(SvgImportError::PathData(s.to_string()))
+    }
+}
+
+/// Reads a transform list, such as `translate(10 5) rotate(30)`
+fn parse_transform(s: &str) -> Result<AffineTransform, SvgImportError> {
+    let mut transform = AffineTransform::identity();
+    for part in s.split(')').filter(|p| !p.trim().is_empty()) {
+        let (name, args) = part
+            .split_once('(')
+            .ok_or_else(|| SvgImportError::PathData(s.to_string()))?;
+        let v = parse_numbers(args)?;
+        let arg = |i: usize, default: f64| v.get(i).copied().unwrap_or(default);
+        let next = match name.trim().trim_start_matches(',').trim() {
+            // x' = a x + c y + e, y' = b x + d y + f
+            "matrix" if v.len() == 6 => AffineTransform::new(v[0], v[2], v[4], v[1], v[3], v[5]),
+            "translate" => AffineTransform::translate(arg(0, 0.), arg(1, 0.)),
+            "scale" => AffineTransform::new(arg(0, 1.), 0., 0., 0., arg(1, arg(0, 1.)), 0.),
+            "rotate" => {
+                let (sin, cos) = (arg(0, 0.) * PI / 180.).sin_cos();
+                let (cx, cy) = (arg(1, 0.), arg(2, 0.));
+                AffineTransform::translate(cx, cy)
+                    .compose(&AffineTransform::new(cos, -sin, 0., sin, cos, 0.))
+                    .compose(&AffineTransform::translate(-cx, -cy))
+            }
+            "skewX" => AffineTransform::new(1., (arg(0, 0.) * PI / 180.).tan(), 0., 0., 1., 0.),
+            "skewY" => AffineTransform::new(1., 0., 0., (arg(0, 0.) * PI / 180.).tan(), 1., 0.),
+            _ => return Err(SvgImportError::PathData(s.to_string())),
+        };
+        transform = transform.compose(&next);
+    }
+    Ok(transform)
+}
+
+/// A cursor over path data
+struct PathReader<'a> {
+    chars: &'a [u8],
+    at: usize,
+}
+
+impl<'a> PathReader<'a> {
+    fn new(s: &'a str) -> Self {
+        PathReader {
+            chars: s.as_bytes(),
+            at: 0,
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.at >= self.chars.len()
+    }
+
+    fn skip_separators(&mut self) {
+        while !self.at_end()
+            && (self.chars[self.at].is_ascii_whitespace() || self.chars[self.at] == b',')
+        {
+            self.at += 1;
+        }
+    }
+
+    /// The next command letter, if the next token is one
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.chars.get(self.at)?;
+        if c.is_ascii_alphabetic() && c != b'e' && c != b'E' {
+            self.at += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        self.skip_separators();
+        let start = self.at;
+        let mut end = start;
+        let (mut dot, mut exp) = (false, false);
+        while end < self.chars.len() {
+            let c = self.chars[end];
+            let sign = (c == b'-' || c == b'+')
+                && (end == start || matches!(self.chars[end - 1], b'e' | b'E'));
+            if c.is_ascii_digit() || sign {
+            } else if c == b'.' && !dot && !exp {
+                dot = true;
+            } else if (c == b'e' || c == b'E') && !exp && end > start {
+                exp = true;
+            } else {
+                break;
+            }
+            end += 1;
+        }
+        let n = std::str::from_utf8(&self.chars[start..end])
+            .ok()?
+            .parse()
+            .ok()?;
+        self.at = end;
+        Some(n)
+    }
+
+    /// An arc flag, which may be written without a separator after it
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let c = *self.chars.get(self.at)?;
+        self.at += 1;
+        match c {
+            b'0' => Some(false),
+            b'1' => Some(true),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn cubic(p0: Coord, p1: Coord, p2: Coord, p3: Coord) -> impl Iterator<Item = Coord> {
+    (1..=CURVE_SEGMENTS).map(move |k| {
+        let t = k as f64 / CURVE_SEGMENTS as f64;
+        let s = 1. - t;
+        p0 * (s * s * s) + p1 * (3. * s * s * t) + p2 * (3. * s * t * t) + p3 * (t * t * t)
+    })
+}
+
+/// Points of an elliptical arc, from its endpoint parameters (SVG 1.1, F.6.5)
+#[allow(clippy::too_many_arguments)]
+fn arc(
+    from: Coord,
+    rx: f64,
+    ry: f64,
+    angle: f64,
+    large: bool,
+    sweep: bool,
+    to: Coord,
+) -> Vec<Coord> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0. || ry == 0. || from == to {
+        return vec![to];
+    }
+    let (sin, cos) = (angle * PI / 180.).sin_cos();
+    let d = (from - to) / 2.;
+    let p = coord! {x: cos * d.x + sin * d.y, y: -sin * d.x + cos * d.y};
+    let lambda = (p.x * p.x) / (rx * rx) + (p.y * p.y) / (ry * ry);
+    if lambda > 1. {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let num = rx * rx * ry * ry - rx * rx * p.y * p.y - ry * ry * p.x * p.x;
+    let den = rx * rx * p.y * p.y + ry * ry * p.x * p.x;
+    let mut k = (num / den).max(0.).sqrt();
+    if large == sweep {
+        k = -k;
+    }
+    let c = coord! {x: k * rx * p.y / ry, y: -k * ry * p.x / rx};
+    let mid = (from + to) / 2.;
+    let center = coord! {x: cos * c.x - sin * c.y + mid.x, y: sin * c.x + cos * c.y + mid.y};
+
+    let angle_of = |u: Coord| u.y.atan2(u.x);
+    let start = angle_of(coord! {x: (p.x - c.x) / rx, y: (p.y - c.y) / ry});
+    let mut delta = angle_of(coord! {x: (-p.x - c.x) / rx, y: (-p.y - c.y) / ry}) - start;
+    if sweep && delta < 0. {
+        delta += 2. * PI;
+    } else if !sweep && delta > 0. {
+        delta -= 2. * PI;
+    }
+    (1..=CURVE_SEGMENTS)
+        .map(|i| {
+            let t = start + delta * i as f64 / CURVE_SEGMENTS as f64;
+            let (x, y) = (rx * t.cos(), ry * t.sin());
+            coord! {x: cos * x - sin * y + center.x, y: sin * x + cos * y + center.y}
+        })
+        .collect()
+}
+
+/// Reads path data into its sub-paths, curves replaced with lines
+fn parse_path(d: &str) -> Result<Vec<LineString>, SvgImportError> {
+    let error = || SvgImportError::PathData(d.to_string());
+    let mut reader = PathReader::new(d);
+    let mut paths: Vec<Vec<Coord>> = Vec::new();
+    let mut current: Vec<Coord> = Vec::new();
+    let mut position = coord! {x: 0., y: 0.};
+    let mut start = position;
+    // Last control point of the previous command, if it was a cubic (C or S),
+    // or a quadratic (Q or T): the smooth curves reflect it
+    let mut last_cubic: Option<Coord> = None;
+    let mut last_quadratic: Option<Coord> = None;
+    let mut command = reader.command().ok_or_else(error)?;
+
+    loop {
+        let relative = command.is_ascii_lowercase();
+        let base = if relative {
+            position
+        } else {
+            coord! {x: 0., y: 0.}
+        };
+        let point = |reader: &mut PathReader| -> Result<Coord, SvgImportError> {
+            let x = reader.number().ok_or_else(error)?;
+            let y = reader.number().ok_or_else(error)?;
+            Ok(base + coord! {x: x, y: y})
+        };
+        let (mut cubic_control, mut quadratic_control) = (None, None);
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                if current.len() > 1 {
+                    paths.push(std::mem::take(&mut current));
+                }
+                position = point(&mut reader)?;
+                start = position;
+                current = vec![position];
+                // Following pairs are lines
+                command = if relative { b'l' } else { b'L' };
+            }
+            b'L' => {
+                position = point(&mut reader)?;
+                current.push(position);
+            }
+            b'H' => {
+                let x = reader.number().ok_or_else(error)?;
+                position.x = if relative { position.x + x } else { x };
+                current.push(position);
+            }
+            b'V' => {
+                let y = reader.number().ok_or_else(error)?;
+                position.y = if relative { position.y + y } else { y };
+                current.push(position);
+            }
+            b'C' | b'S' => {
+                let c1 = if command.eq_ignore_ascii_case(&b'C') {
+                    point(&mut reader)?
+                } else {
+                    last_cubic.map(|c| position * 2. - c).unwrap_or(position)
+                };
+                let c2 = point(&mut reader)?;
+                let end = point(&mut reader)?;
+                current.extend(cubic(position, c1, c2, end));
+                cubic_control = Some(c2);
+                position = end;
+            }
+            b'Q' | b'T' => {
+                let q = if command.eq_ignore_ascii_case(&b'Q') {
+                    point(&mut reader)?
+                } else {
+                    last_quadratic
+                        .map(|c| position * 2. - c)
+                        .unwrap_or(position)
+                };
+                let end = point(&mut reader)?;
+                // The same curve as a cubic
+                let c1 = position + (q - position) * (2. / 3.);
+                let c2 = end + (q - end) * (2. / 3.);
+                current.extend(cubic(position, c1, c2, end));
+                quadratic_control = Some(q);
+                position = end;
+            }
+            b'A' => {
+                let rx = reader.number().ok_or_else(error)?;
+                let ry = reader.number().ok_or_else(error)?;
+                let angle = reader.number().ok_or_else(error)?;
+                let large = reader.flag().ok_or_else(error)?;
+                let sweep = reader.flag().ok_or_else(error)?;
+                let end = point(&mut reader)?;
+                current.extend(arc(position, rx, ry, angle, large, sweep, end));
+                position = end;
+            }
+            b'Z' => {
+                if current.len() > 1 {
+                    paths.push(std::mem::take(&mut current));
+                }
+                position = start;
+                current = vec![position];
+            }
+            _ => return Err(error()),
+        }
+        (last_cubic, last_quadratic) = (cubic_control, quadratic_control);
+
+        // A new command, or more arguments for the same one
+        match reader.command() {
+            Some(c) => command = c,
+            None if reader.at_end() => break,
+            None if command.eq_ignore_ascii_case(&b'Z') => return Err(error()),
+            None => {}
+        }
+    }
+    if current.len() > 1 {
+        paths.push(current);
+    }
+    Ok(paths.into_iter().map(LineString::new).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::grid::HoneycombGrid;
+    use crate::hexagon::Hexagon;
+    use crate::svg::{save_polygon_as_svg, LayeredSvg};
+    use crate::test_support::{assert_valid, nothing};
+    use crate::test_support::{make_base, make_hexagon_config};
+    use geo::Area;
+    use geo_types::point;
+
+    const MOTIF: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+  <rect id="cell" x="0" y="0" width="4" height="2" transform="scale(2.5 5)"/>
+  <g transform="translate(1 1)">
+    <path d="M 0 0 L 3 0 L 3 3 Z m 4 0 h 3 v 3 h -3 z"/>
+    <polygon points="1,5 2,5 2,6" transform="rotate(90 1.5 5.5)"/>
+  </g>
+  <defs><rect x="0" y="0" width="1" height="1"/></defs>
+</svg>"#;
+
+    fn close(a: Coord, b: Coord) -> bool {
+        (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+    }
+
+    #[test]
+    fn read_motif() {
+        let unit = SvgImport::new("cell").read_unit(MOTIF).unwrap();
+
+        assert!(close(coord! {x: 10., y: 10.}, unit.exterior().0[2]));
+        assert_eq!(3, unit.interiors().len());
+        // Two sub-paths, the second one relative
+        assert!(close(coord! {x: 1., y: 1.}, unit.interiors()[0].0[0]));
+        assert!(close(coord! {x: 5., y: 1.}, unit.interiors()[1].0[0]));
+        assert_eq!(
+            4.5 + 9.,
+            unit.interiors()[..2].iter().map(ring_area).sum::<f64>()
+        );
+        // The triangle turned round its middle, then moved
+        assert!(close(coord! {x: 3., y: 6.}, unit.interiors()[2].0[0]));
+        assert!(unit.interiors().iter().all(|r| r.is_closed()));
+    }
+
+    #[test]
+    fn path_commands() {
+        let rings = parse_path("M0,0C0,1 1,1 1,0S2-1 2,0Q3,1 4,0T6,0A1 1 0 01 8,0Z").unwrap();
+        assert_eq!(1, rings.len());
+        let last = *rings[0].0.last().unwrap();
+        assert!(close(coord! {x: 8., y: 0.}, last));
+        // The arc is a half circle above the line, y being down
+        let top = rings[0].0.iter().map(|c| c.y).fold(f64::MAX, f64::min);
+        assert!((top + 1.).abs() < 1e-2);
+
+        assert!(parse_path("M 0 0 L 1").is_err());
+        assert_eq!(
+            Err(SvgImportError::NoBoundary("none".to_string())),
+            SvgImport::new("none").read_unit(MOTIF)
+        );
+    }
+
+    #[test]
+    fn smooth_after_other_curve() {
+        // The S follows a Q: its first control point is the current point
+        let rings = parse_path("M0,0 Q1,1 2,0 S3,1 4,0").unwrap();
+        let middle = rings[0].0[CURVE_SEGMENTS + CURVE_SEGMENTS / 2];
+        assert!(close(coord! {x: 2.625, y: 0.375}, middle));
+    }
+
+    #[test]
+    fn uses_and_units() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <rect id="cell" width="1in" height="96"/>
+  <defs><rect id="hole" x="1" y="1" width="2.54cm" height="6pt"/></defs>
+  <use xlink:href="#hole" x="2" y="3"/>
+  <use href="#hole" transform="translate(5 0)" x="1"/>
+</svg>"##;
+
+        let unit = SvgImport::new("cell").read_unit(svg).unwrap();
+
+        assert!(close(coord! {x: 96., y: 96.}, unit.exterior().0[2]));
+        assert_eq!(2, unit.interiors().len());
+        assert!(close(coord! {x: 3., y: 4.}, unit.interiors()[0].0[0]));
+        assert!(close(coord! {x: 99., y: 12.}, unit.interiors()[0].0[2]));
+        assert!(close(coord! {x: 7., y: 1.}, unit.interiors()[1].0[0]));
+
+        let relative = svg.replace("2.54cm", "10%");
+        assert_eq!(
+            Err(SvgImportError::Length("10%".to_string())),
+            SvgImport::new("cell").read_unit(&relative)
+        );
+        let cycle =
+            r##"<svg xmlns="http://www.w3.org/2000/svg"><g id="a"><use href="#a"/></g></svg>"##;
+        assert_eq!(
+            Err(SvgImportError::Reference("#a".to_string())),
+            SvgImport::new("a").read_unit(cycle)
+        );
+    }
+
+    #[test]
+    fn round_trip_hexagon() {
+        // Given a hexagon written by this library, its exterior in the frame layer
+        let hexa = Hexagon::new_at_base(&make_base(), &make_hexagon_config());
+        let svg = LayeredSvg::new()
+            .with_cut_path(&crate::cutpath::CutPath::from_polygon(&hexa.polygon))
+            .to_string();
+
+        // When
+        let unit = SvgImport::new("frame").read_unit(&svg).unwrap();
+
+        // Then
+        assert_eq!(hexa.polygon.interiors().len(), unit.interiors().len());
+        assert!((hexa.polygon.unsigned_area() - unit.unsigned_area()).abs() < 1e-2);
+
+        // and it can be tiled
+        let base = make_base();
+        let (dx, dy) = (base.u.x(), 3. * base.u.y());
+        let mut grid = HoneycombGrid::new(dx, dy, 3, 3);
+        let frame = SimpleFrame::new(point! {x: 0., y: 0.}, 4. * dx, 2. * dy, 1.0);
+        let figure = frame.frame(&grid.fill_with_unit(&unit));
+        assert!(!figure.interiors().is_empty());
+        save_polygon_as_svg(&figure, "test_figures/imported_plane.svg");
+        assert_valid(&figure, nothing);
+    }
+}
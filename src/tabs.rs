@@ -0,0 +1,186 @@
+use geo::{Area, EuclideanLength};
+use geo_types::{Coord, LineString, Polygon};
+
+use crate::cutpath::{Cut, CutPath, Ring};
+
+/// Holding tabs (micro-joints): short lengths of a ring that are left uncut, so
+/// that the part, or the piece falling out of a hole, stays in the sheet until
+/// it is broken out by hand.
+///
+/// ```text
+///   +-----   -----   -----+
+///   |                     |
+///                           <- tab
+///   |                     |
+///   +-----   -----   -----+
+/// ```
+pub struct HoldingTabs {
+    /// Length of material left uncut for each tab
+    pub width: f64,
+    /// Number of tabs on the exterior
+    pub count: usize,
+    /// Holes of at least this area get tabs too
+    pub hole_min_area: Option<f64>,
+    /// Number of tabs on a large hole
+    pub hole_count: usize,
+}
+
+impl Default for HoldingTabs {
+    fn default() -> HoldingTabs {
+        HoldingTabs {
+            width: 0.3,
+            count: 4,
+            hole_min_area: None,
+            hole_count: 2,
+        }
+    }
+}
+
+impl HoldingTabs {
+    /// Cuts the tabs out of a cut path.  Each ring that gets tabs is replaced by
+    /// the open lines between its tabs, in the same place of the path.
+    pub fn apply(&self, path: &CutPath) -> CutPath {
+        let mut cuts = Vec::new();
+        for cut in path.cuts.iter() {
+            let count = self.count_for(cut);
+            let length = cut.line.euclidean_length();
+            if count == 0 || !cut.closed || self.width * count as f64 >= length {
+                cuts.push(cut.clone());
+                continue;
+            }
+            // Tabs are evenly spaced, the first one half a spacing after the start
+            let spacing = length / count as f64;
+            for k in 0..count {
+                let from = (k as f64 + 0.5) * spacing + self.width / 2.;
+                let to = (k as f64 + 1.5) * spacing - self.width / 2.;
+                cuts.push(Cut {
+                    polygon: cut.polygon,
+                    ring: cut.ring,
+                    line: sub_line(&cut.line, from, to),
+                    closed: false,
+                });
+            }
+        }
+        CutPath { cuts }
+    }
+
+    fn count_for(&self, cut: &Cut) -> usize {
+        match cut.ring {
+            Ring::Exterior => self.count,
+            Ring::Interior(_) => match self.hole_min_area {
+                Some(min_area)
+                    if Polygon::new(cut.line.clone(), vec![]).unsigned_area() >= min_area =>
+                {
+                    self.hole_count
+                }
+                _ => 0,
+            },
+        }
+    }
+}
+
+/// Part of a closed ring between two distances from its start.  The distances
+/// may go past the length of the ring, in which case the ring is followed round.
+fn sub_line(ring: &LineString, from: f64, to: f64) -> LineString {
+    let length = ring.euclidean_length();
+    let segments: Vec<(Coord, Coord, f64)> = ring
+        .lines()
+        .map(|l| (l.start, l.end, l.euclidean_length()))
+        .collect();
+
+    let mut coords: Vec<Coord> = Vec::new();
+    let mut position = 0.;
+    let mut turn = 0.;
+    // Two turns are enough for `to` to be reached
+    for (a, b, len) in segments.iter().chain(segments.iter()) {
+        let (start, end) = (position + turn, position + turn + len);
+        let point_at = |d: f64| *a + (*b - *a) * ((d - start) / len);
+        if end > from && start < to && *len > 0. {
+            if coords.is_empty() {
+                coords.push(point_at(from.max(start)));
+            }
+            if end < to {
+                coords.push(*b);
+            } else {
+                coords.push(point_at(to));
+                break;
+            }
+        }
+        position += len;
+        if position >= length {
+            position = 0.;
+            turn += length;
+        }
+    }
+    LineString::new(coords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{Frame, SimpleFrame};
+    use crate::lightburn::save_cut_path_as_lbrn2;
+    use crate::svg::save_cut_path_as_svg;
+    use crate::test_support::{assert_valid, holes_either_way, nothing};
+    use geo_types::{coord, point, polygon};
+
+    #[test]
+    fn tabs_on_square() {
+        // Given a 10x10 square, with a large and a small hole
+        let square = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.), (x: 0., y: 10.)],
+            interiors: [
+                [(x: 2., y: 2.), (x: 6., y: 2.), (x: 6., y: 6.), (x: 2., y: 6.)],
+                [(x: 7., y: 7.), (x: 8., y: 7.), (x: 8., y: 8.)],
+            ],
+        );
+        let tabs = HoldingTabs {
+            width: 1.,
+            count: 4,
+            hole_min_area: Some(4.),
+            hole_count: 2,
+        };
+        assert_valid(&square, holes_either_way);
+
+        // When
+        let path = tabs.apply(&CutPath::from_polygon(&square));
+
+        // Then the small hole is cut whole, then the large hole in 2 parts,
+        // and the exterior in 4 parts, each one tab shorter than a side
+        let rings: Vec<(Ring, bool)> = path.cuts.iter().map(|c| (c.ring, c.closed)).collect();
+        assert_eq!(
+            vec![
+                (Ring::Interior(0), false),
+                (Ring::Interior(0), false),
+                (Ring::Interior(1), true),
+                (Ring::Exterior, false),
+                (Ring::Exterior, false),
+                (Ring::Exterior, false),
+                (Ring::Exterior, false),
+            ],
+            rings
+        );
+        let exterior: Vec<&Cut> = path.cuts.iter().skip(3).collect();
+        for cut in exterior.iter() {
+            assert!((cut.line.euclidean_length() - 9.).abs() < 1e-9);
+        }
+        // The exterior starts at (10, 10), next to the last hole: the first tab is
+        // in the middle of the top side
+        assert_eq!(coord! {x: 4.5, y: 10.}, exterior[0].line.0[0]);
+    }
+
+    #[test]
+    fn tabs_on_frame() {
+        let frame = SimpleFrame::new(point! {x: 0., y: 0.}, 8., 6., 1.);
+        let figure = frame.frame(&[]);
+        assert_valid(&figure, nothing);
+
+        let path = HoldingTabs::default().apply(&CutPath::from_polygon(&figure));
+
+        assert_eq!(4, path.cuts.len());
+        let cut: f64 = path.cuts.iter().map(|c| c.line.euclidean_length()).sum();
+        assert!((cut - (2. * (10. + 8.) - 4. * 0.3)).abs() < 1e-9);
+        save_cut_path_as_svg(&path, "test_figures/tabs_frame.svg");
+        save_cut_path_as_lbrn2(&path, "test_figures/tabs_frame.lbrn2");
+    }
+}
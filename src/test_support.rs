@@ -0,0 +1,124 @@
+//! Figures shared by the tests of several modules
+
+use std::f64::consts::PI;
+
+use geo_types::{coord, point, Polygon};
+
+use crate::curves::{CurvePath, Segment};
+use crate::cutpath::Ring;
+use crate::hexagon::HexagonConfig;
+use crate::kumiko::{Base, KumikoConfig};
+use crate::precision::GeometryContext;
+use crate::triskell::TriskellConfig;
+use crate::validity::{repair, validate, Violation};
+
+/// The base of the hexagon tests: a rhombus of side 4
+pub(crate) fn make_base() -> Base {
+    let side: f64 = 4.0;
+    let side_r3o2 = side * f64::sqrt(3.0) / 2.0;
+    let side_1o2 = side / 2.0;
+
+    Base::new(
+        point! { x: 0., y: 0. },
+        point! {x: side_r3o2, y: side_1o2},
+        point! {x: -side_r3o2, y: side_1o2},
+    )
+}
+
+/// The base of the triskell tests: a rhombus of side 4 standing on its
+/// vertical side
+pub(crate) fn make_triskell_base() -> Base {
+    let side: f64 = 4.0;
+
+    Base::new(
+        point! { x: 0., y: 0. },
+        point! {x: side * f64::sqrt(3.0) / 2.0, y: side / 2.0},
+        point! {x: 0., y: side},
+    )
+}
+
+pub(crate) fn make_hexagon_config() -> HexagonConfig {
+    HexagonConfig {
+        space: 0.75,
+        parent: KumikoConfig::default(),
+    }
+}
+
+pub(crate) fn make_triskell_config() -> TriskellConfig {
+    TriskellConfig {
+        space: 0.75,
+        parent: KumikoConfig::default(),
+    }
+}
+
+/// A drop: a half circle, then a bezier down to a point
+pub(crate) fn make_drop() -> CurvePath {
+    CurvePath::new(vec![
+        Segment::Arc {
+            center: coord! {x: 0., y: 0.},
+            radius: 1.,
+            start: 0.,
+            sweep: PI,
+        },
+        Segment::Cubic {
+            from: coord! {x: -1., y: 0.},
+            ctrl1: coord! {x: -1., y: -1.},
+            ctrl2: coord! {x: 0., y: -1.5},
+            to: coord! {x: 0., y: -2.},
+        },
+        Segment::Line {
+            from: coord! {x: 0., y: -2.},
+            to: coord! {x: 1., y: 0.},
+        },
+    ])
+}
+
+/// Asserts that the output of a test breaks none of the rules of `validate`
+/// but the ones `allowed`, and that `repair` fixes those, but the crossings it
+/// leaves
+pub(crate) fn assert_valid(polygon: &Polygon, allowed: fn(&Violation) -> bool) {
+    let context = GeometryContext::for_geometry(polygon);
+    let violations = validate(polygon, context.tolerance);
+    let forbidden: Vec<&Violation> = violations.iter().filter(|v| !allowed(v)).collect();
+    assert!(forbidden.is_empty(), "unexpected violations: {forbidden:?}");
+    if violations.is_empty() {
+        return;
+    }
+    let crossing = |v: &Violation| {
+        allowed(v)
+            && matches!(
+                v,
+                Violation::SelfIntersection(_) | Violation::RingsCross(..)
+            )
+    };
+    for repaired in repair(polygon, &context).iter() {
+        let left = validate(repaired, context.tolerance);
+        let unrepaired: Vec<&Violation> = left.iter().filter(|v| !crossing(v)).collect();
+        assert!(
+            unrepaired.is_empty(),
+            "unrepaired violations: {unrepaired:?}"
+        );
+    }
+}
+
+/// Allows no violation
+pub(crate) fn nothing(_: &Violation) -> bool {
+    false
+}
+
+/// The figures keep their holes wound as they come, and only the framing
+/// orients them
+pub(crate) fn holes_either_way(violation: &Violation) -> bool {
+    matches!(violation, Violation::WrongWinding(Ring::Interior(_)))
+}
+
+/// Inside a corner, the shifted line and arc overlap instead of leaving a gap,
+/// and the line joining them makes a small loop
+pub(crate) fn inner_corner_loop(violation: &Violation) -> bool {
+    matches!(violation, Violation::SelfIntersection(Ring::Exterior))
+}
+
+/// The imports keep the rings wound as the file draws them
+pub(crate) fn windings_as_drawn(violation: &Violation) -> bool {
+    matches!(violation, Violation::WrongWinding(_))
+}
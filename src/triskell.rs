@@ -3,6 +3,7 @@ use crate::oglines::{linestring_from_lines, InfiniteLine, LinesLR};
 use geo::{AffineOps, AffineTransform};
 use geo_types::{LineString, MultiLineString, Point, Polygon};
 
+#[derive(Clone)]
 pub struct Triskell {
     polygon: Polygon,
     pub rays: MultiLineString,
@@ -10,8 +11,8 @@ pub struct Triskell {
 }
 
 pub struct TriskellConfig {
-    space: f64,
-    parent: KumikoConfig,
+    pub(crate) space: f64,
+    pub(crate) parent: KumikoConfig,
 }
 
 pub trait TriskellConfigTrait: KumikoConfigTrait {
@@ -107,6 +108,7 @@ mod tests {
     use crate::kumiko::{Base, KumikoConfig, KumikoFigure};
     use crate::oglines::points2geometry;
     use crate::svg::write_svg;
+    use crate::test_support::{assert_valid, holes_either_way};
     use geo_svg::{Color, ToSvg};
     use geo_types::point;
 
@@ -145,6 +147,7 @@ mod tests {
             .with_stroke_width(0.01)
             .with_stroke_color(Color::Rgb(100, 0, 200))
             .with_fill_opacity(0.2);
+        assert_valid(triskell.polygon(), holes_either_way);
         let svg_eventail = triskell.draw_figure();
 
         let svg = svg_eventail //
@@ -0,0 +1,525 @@
+use std::fmt;
+
+use geo::coordinate_position::{CoordPos, CoordinatePosition};
+use geo::line_intersection::{line_intersection, LineIntersection};
+use geo::orient::{Direction, Orient};
+use geo::{Area, Winding};
+use geo_types::{Coord, Line, LineString, MultiPolygon, Polygon};
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
+
+use crate::cutpath::Ring;
+use crate::merge::{merge_touching_holes, shared_edges};
+use crate::operations::thickness;
+use crate::precision::GeometryContext;
+
+/// A break of the OGC simple features rules by a polygon, for the ring at fault
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Violation {
+    /// A ring with less than three distinct vertices
+    TooFewPoints(Ring),
+    /// Two consecutive vertices closer than the tolerance, as a closing point
+    /// repeated.  `index` is that of the second one.
+    RepeatedPoint { ring: Ring, index: usize },
+    /// A ring thinner than the tolerance, with no area to cut
+    ZeroArea(Ring),
+    /// The exterior is not counter-clockwise, or a hole not clockwise
+    WrongWinding(Ring),
+    /// A ring crossing or touching itself, or turning back on an edge
+    SelfIntersection(Ring),
+    /// Two rings crossing each other
+    RingsCross(Ring, Ring),
+    /// Two rings running along each other: the line between them would be cut
+    /// twice
+    RingsOverlap(Ring, Ring),
+    /// A hole lying outside the exterior
+    HoleOutside(usize),
+    /// A hole lying inside another hole
+    NestedHole { hole: usize, inside: usize },
+}
+
+fn ring_name(ring: &Ring) -> String {
+    match ring {
+        Ring::Exterior => "exterior".to_string(),
+        Ring::Interior(i) => format!("hole {}", i),
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::TooFewPoints(r) => write!(f, "{} has too few points", ring_name(r)),
+            Violation::RepeatedPoint { ring, index } => {
+                write!(f, "{} repeats its point {}", ring_name(ring), index)
+            }
+            Violation::ZeroArea(r) => write!(f, "{} has no area", ring_name(r)),
+            Violation::WrongWinding(r) => write!(f, "{} is wound the wrong way", ring_name(r)),
+            Violation::SelfIntersection(r) => write!(f, "{} crosses itself", ring_name(r)),
+            Violation::RingsCross(a, b) => {
+                write!(f, "{} crosses {}", ring_name(a), ring_name(b))
+            }
+            Violation::RingsOverlap(a, b) => {
+                write!(f, "{} runs along {}", ring_name(a), ring_name(b))
+            }
+            Violation::HoleOutside(i) => write!(f, "hole {} is outside the exterior", i),
+            Violation::NestedHole { hole, inside } => {
+                write!(f, "hole {} is inside hole {}", hole, inside)
+            }
+        }
+    }
+}
+
+fn ring_of(index: usize) -> Ring {
+    match index {
+        0 => Ring::Exterior,
+        i => Ring::Interior(i - 1),
+    }
+}
+
+fn distance(a: Coord, b: Coord) -> f64 {
+    let d = a - b;
+    (d.x * d.x + d.y * d.y).sqrt()
+}
+
+/// Number of distinct vertices of a ring
+fn vertex_count(ring: &LineString) -> usize {
+    match ring.0.len() {
+        0 => 0,
+        n if ring.is_closed() => n - 1,
+        n => n,
+    }
+}
+
+/// Whether a ring is too thin to have an area, or has no length at all
+fn flat(ring: &LineString, tolerance: f64) -> bool {
+    let thickness = thickness(&Polygon::new(ring.clone(), vec![]));
+    thickness.is_nan() || thickness < tolerance
+}
+
+/// Where a ring lies compared to another one: inside it when one of its
+/// vertices is, outside when one is and none is inside
+fn position(ring: &LineString, other: &LineString) -> CoordPos {
+    let other = Polygon::new(other.clone(), vec![]);
+    let positions: Vec<CoordPos> = ring
+        .coords()
+        .map(|c| other.coordinate_position(c))
+        .collect();
+    if positions.contains(&CoordPos::Inside) {
+        CoordPos::Inside
+    } else if positions.contains(&CoordPos::Outside) {
+        CoordPos::Outside
+    } else {
+        CoordPos::OnBoundary
+    }
+}
+
+type Edge = GeomWithData<Line, (usize, usize)>;
+
+/// The crossings of the edges: those of a ring that are not neighbours, and
+/// those of two rings that cut through each other
+fn crossings(rings: &[&LineString]) -> Vec<Violation> {
+    let edges: Vec<Edge> = rings
+        .iter()
+        .enumerate()
+        .flat_map(|(r, ring)| {
+            ring.lines()
+                .enumerate()
+                .filter(|(_, l)| l.start != l.end)
+                .map(move |(i, l)| GeomWithData::new(l, (r, i)))
+        })
+        .collect();
+    let tree = RTree::bulk_load(edges);
+
+    let mut violations = Vec::new();
+    for (a, b) in tree.intersection_candidates_with_other_tree(&tree) {
+        let ((ra, ia), (rb, ib)) = (a.data, b.data);
+        if (ra, ia) >= (rb, ib) {
+            continue;
+        }
+        let Some(intersection) = line_intersection(*a.geom(), *b.geom()) else {
+            continue;
+        };
+        let violation = if ra == rb {
+            let n = rings[ra].lines().count();
+            let neighbours = ib - ia == 1 || (ia == 0 && ib == n - 1);
+            match intersection {
+                LineIntersection::SinglePoint { .. } if neighbours => continue,
+                _ => Violation::SelfIntersection(ring_of(ra)),
+            }
+        } else {
+            match intersection {
+                LineIntersection::SinglePoint {
+                    is_proper: true, ..
+                } => Violation::RingsCross(ring_of(ra), ring_of(rb)),
+                _ => continue,
+            }
+        };
+        if !violations.contains(&violation) {
+            violations.push(violation);
+        }
+    }
+    violations
+}
+
+/// Lists the violations of the OGC simple features rules by a polygon, ring
+/// by ring.  The points closer than the tolerance are taken as repeated, and
+/// the rings thinner than it as flat.
+///
+/// The exterior is expected counter-clockwise and the holes clockwise, as
+/// oriented by `Direction::Default`.  The rings may touch at a point, but not
+/// cross nor run along each other.
+pub fn validate(polygon: &Polygon, tolerance: f64) -> Vec<Violation> {
+    let rings: Vec<&LineString> = std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .collect();
+    let mut violations = Vec::new();
+    for (r, ring) in rings.iter().enumerate() {
+        let name = ring_of(r);
+        if vertex_count(ring) < 3 {
+            violations.push(Violation::TooFewPoints(name));
+            continue;
+        }
+        for (index, pair) in ring.0.windows(2).enumerate() {
+            if distance(pair[0], pair[1]) < tolerance {
+                violations.push(Violation::RepeatedPoint {
+                    ring: name,
+                    index: index + 1,
+                });
+            }
+        }
+        if flat(ring, tolerance) {
+            violations.push(Violation::ZeroArea(name));
+            continue;
+        }
+        let wrong = match name {
+            Ring::Exterior => ring.is_cw(),
+            Ring::Interior(_) => ring.is_ccw(),
+        };
+        if wrong {
+            violations.push(Violation::WrongWinding(name));
+        }
+    }
+    violations.extend(crossings(&rings));
+    for shared in shared_edges(polygon, tolerance) {
+        violations.push(Violation::RingsOverlap(shared.rings.0, shared.rings.1));
+    }
+
+    let holes = polygon.interiors();
+    if vertex_count(polygon.exterior()) >= 3 {
+        for (i, hole) in holes.iter().enumerate() {
+            if position(hole, polygon.exterior()) == CoordPos::Outside {
+                violations.push(Violation::HoleOutside(i));
+            }
+        }
+    }
+    for (hole, inside) in nested_holes(holes) {
+        violations.push(Violation::NestedHole { hole, inside });
+    }
+    violations
+}
+
+/// The holes lying inside another hole, with the index of that one
+fn nested_holes(holes: &[LineString]) -> Vec<(usize, usize)> {
+    let boxes: Vec<GeomWithData<Polygon, usize>> = holes
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| vertex_count(h) >= 3)
+        .map(|(i, h)| GeomWithData::new(Polygon::new(h.clone(), vec![]), i))
+        .collect();
+    let tree = RTree::bulk_load(boxes);
+    let mut nested = Vec::new();
+    for (a, b) in tree.intersection_candidates_with_other_tree(&tree) {
+        if a.data != b.data && position(&holes[a.data], &holes[b.data]) == CoordPos::Inside {
+            nested.push((a.data, b.data));
+        }
+    }
+    nested.sort();
+    nested
+}
+
+/// Removes the points closer than the tolerance to the previous one, and
+/// closes the ring
+fn without_repeated_points(ring: &LineString, tolerance: f64) -> LineString {
+    let mut coords: Vec<Coord> = Vec::with_capacity(ring.0.len());
+    for c in ring.coords() {
+        if coords
+            .last()
+            .is_none_or(|last| distance(*last, *c) >= tolerance)
+        {
+            coords.push(*c);
+        }
+    }
+    while coords.len() > 1 && distance(coords[0], *coords.last().unwrap()) < tolerance {
+        coords.pop();
+    }
+    let mut ring = LineString::new(coords);
+    ring.close();
+    ring
+}
+
+/// Removes the points closer than the tolerance to the previous one, from
+/// every ring of a polygon
+pub fn without_repeated_vertices(polygon: &Polygon, tolerance: f64) -> Polygon {
+    Polygon::new(
+        without_repeated_points(polygon.exterior(), tolerance),
+        polygon
+            .interiors()
+            .iter()
+            .map(|h| without_repeated_points(h, tolerance))
+            .collect(),
+    )
+}
+
+/// Splits a ring into the loops it makes between the vertices it passes
+/// twice, closed
+fn loops(ring: &LineString) -> Vec<LineString> {
+    let mut loops = Vec::new();
+    let mut stack: Vec<Coord> = Vec::new();
+    let n = vertex_count(ring);
+    for c in ring.0.iter().take(n) {
+        if let Some(k) = stack.iter().position(|s| s == c) {
+            let mut pinched: Vec<Coord> = stack.split_off(k + 1);
+            pinched.insert(0, *c);
+            pinched.push(*c);
+            loops.push(LineString::new(pinched));
+        } else {
+            stack.push(*c);
+        }
+    }
+    if !stack.is_empty() {
+        stack.push(stack[0]);
+        loops.insert(0, LineString::new(stack));
+    }
+    loops
+}
+
+/// Splits an exterior touching itself into its loops: those inside another
+/// one are holes of it, and the others exteriors of their own, the largest
+/// first
+fn split_exterior(exterior: LineString) -> (Vec<LineString>, Vec<LineString>) {
+    let parts = loops(&exterior);
+    let (mut outer, mut inner) = (Vec::new(), Vec::new());
+    for (i, part) in parts.iter().enumerate() {
+        let hole = parts
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && position(part, other) == CoordPos::Inside);
+        if hole {
+            inner.push(part.clone());
+        } else {
+            outer.push(part.clone());
+        }
+    }
+    let area = |r: &LineString| Polygon::new(r.clone(), vec![]).unsigned_area();
+    outer.sort_by(|a, b| area(b).total_cmp(&area(a)));
+    (outer, inner)
+}
+
+/// Splits a hole touching itself into the holes its loops make side by side,
+/// when none is inside another
+fn split_hole(hole: LineString) -> Vec<LineString> {
+    let parts = loops(&hole);
+    let apart = parts.iter().enumerate().all(|(i, a)| {
+        parts
+            .iter()
+            .enumerate()
+            .all(|(j, b)| i == j || position(a, b) != CoordPos::Inside)
+    });
+    if apart {
+        parts
+    } else {
+        vec![hole]
+    }
+}
+
+/// Fixes what can be fixed in a polygon before it is exported:
+///
+/// - the repeated points are removed, and the rings closed
+/// - the rings touching themselves are split into their loops.  The loops of
+///   the exterior that lie side by side become polygons of their own, held to
+///   the others by a point only.
+/// - the rings with too few points or no area are dropped, as the holes
+///   outside the exterior or inside another hole
+/// - the holes running along each other are merged
+/// - the rings are wound as `validate` expects
+///
+/// The crossings are left, and still reported by `validate`.
+pub fn repair(polygon: &Polygon, context: &GeometryContext) -> MultiPolygon {
+    let tolerance = context.tolerance;
+    let flat = |r: &LineString| vertex_count(r) < 3 || flat(r, tolerance);
+    let exterior = without_repeated_points(polygon.exterior(), tolerance);
+    let (exteriors, inner) = split_exterior(exterior);
+    let holes: Vec<LineString> = polygon
+        .interiors()
+        .iter()
+        .flat_map(|h| split_hole(without_repeated_points(h, tolerance)))
+        .chain(inner)
+        .filter(|h| !flat(h))
+        .collect();
+    let nested: Vec<usize> = nested_holes(&holes).into_iter().map(|(h, _)| h).collect();
+    let holes: Vec<LineString> = holes
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !nested.contains(i))
+        .map(|(_, h)| h)
+        .collect();
+    exteriors
+        .into_iter()
+        .filter(|e| !flat(e))
+        .map(|exterior| {
+            let inside = holes
+                .iter()
+                .filter(|h| position(h, &exterior) == CoordPos::Inside)
+                .cloned()
+                .collect();
+            let polygon = Polygon::new(exterior, inside);
+            merge_touching_holes(&polygon, context).orient(Direction::Default)
+        })
+        .collect()
+}
+
+/// Repairs the polygons of a figure, each in the context of its own extent.
+/// The export takes the polygons as they are: call it first, as in
+/// `CutPath::plan(&repair_all(&polygons), from)`, to cut the repaired ones.
+pub fn repair_all(polygons: &[Polygon]) -> Vec<Polygon> {
+    polygons
+        .iter()
+        .flat_map(|p| repair(p, &GeometryContext::for_geometry(p)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::polygon;
+
+    fn square(x: f64, y: f64, side: f64) -> LineString {
+        LineString::from(vec![
+            (x, y),
+            (x, y + side),
+            (x + side, y + side),
+            (x + side, y),
+            (x, y),
+        ])
+    }
+
+    #[test]
+    fn violations_of_rings() {
+        // A plate wound clockwise, with a hole repeating its closing point, a
+        // flat one, one outside, one inside another, and a bow tie
+        let plate = Polygon::new(
+            square(0., 0., 10.),
+            vec![
+                LineString::from(vec![(1., 1.), (1., 2.), (2., 2.), (2., 1.), (1., 1.00001)]),
+                LineString::from(vec![(3., 1.), (4., 1.), (5., 1.)]),
+                square(12., 1., 1.),
+                square(3., 3., 3.),
+                square(4., 4., 1.),
+                LineString::from(vec![(7., 7.), (9., 9.), (9., 7.), (7., 8.)]),
+            ],
+        );
+        let context = GeometryContext::for_geometry(&plate);
+
+        let violations = validate(&plate, context.tolerance);
+        for violation in violations.iter() {
+            println!("{violation}");
+        }
+        assert!(violations.contains(&Violation::WrongWinding(Ring::Exterior)));
+        assert!(violations.contains(&Violation::RepeatedPoint {
+            ring: Ring::Interior(0),
+            index: 5
+        }));
+        assert!(violations.contains(&Violation::ZeroArea(Ring::Interior(1))));
+        assert!(violations.contains(&Violation::HoleOutside(2)));
+        assert!(violations.contains(&Violation::NestedHole { hole: 4, inside: 3 }));
+        assert!(violations.contains(&Violation::SelfIntersection(Ring::Interior(5))));
+        assert!(!violations.contains(&Violation::WrongWinding(Ring::Interior(3))));
+
+        // Only the bow tie is left
+        let repaired = &repair(&plate, &context).0[0];
+        assert_eq!(3, repaired.interiors().len());
+        assert_eq!(
+            vec![Violation::SelfIntersection(Ring::Interior(2))],
+            validate(repaired, context.tolerance)
+        );
+    }
+
+    #[test]
+    fn violations_between_rings() {
+        // Two holes sharing an edge, and one crossing the exterior
+        let plate = Polygon::new(
+            square(0., 0., 10.).0.into_iter().rev().collect(),
+            vec![square(1., 1., 1.), square(2., 1., 1.), square(9., 4., 2.)],
+        );
+        let context = GeometryContext::for_geometry(&plate);
+
+        let violations = validate(&plate, context.tolerance);
+        assert_eq!(
+            vec![
+                Violation::RingsCross(Ring::Exterior, Ring::Interior(2)),
+                Violation::RingsOverlap(Ring::Interior(0), Ring::Interior(1)),
+            ],
+            violations
+        );
+        assert_eq!(
+            "hole 0 runs along hole 1",
+            Violation::RingsOverlap(Ring::Interior(0), Ring::Interior(1)).to_string()
+        );
+
+        let repaired = &repair(&plate, &context).0[0];
+        assert_eq!(2, repaired.interiors().len());
+        assert_eq!(
+            vec![Violation::RingsCross(Ring::Exterior, Ring::Interior(1))],
+            validate(repaired, context.tolerance)
+        );
+
+        let valid = polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 0., y: 1.)];
+        assert!(validate(&valid, context.tolerance).is_empty());
+        assert_eq!(vec![valid.clone()], repair_all(&[valid]));
+    }
+
+    #[test]
+    fn pinched_rings() {
+        // Given an exterior going around a notch that touches it at a point,
+        // and around a lobe held by a point
+        //
+        //  +-------+
+        //  |  /\   |
+        //  | /  \  |
+        //  +-------+---+
+        //          |   |
+        //          +---+
+        let plate = Polygon::new(
+            LineString::from(vec![
+                (0., 0.),
+                (4., 0.),
+                (4., -2.),
+                (6., -2.),
+                (6., 0.),
+                (4., 0.),
+                (4., 4.),
+                (0., 4.),
+                (0., 0.),
+                (2., 3.),
+                (3., 1.),
+                (0., 0.),
+            ]),
+            vec![],
+        );
+        let context = GeometryContext::for_geometry(&plate);
+        let violations = validate(&plate, context.tolerance);
+        assert!(violations.contains(&Violation::SelfIntersection(Ring::Exterior)));
+
+        // When
+        let repaired = repair(&plate, &context);
+
+        // Then the notch is a hole, and the lobe a polygon of its own
+        assert_eq!(2, repaired.0.len());
+        assert_eq!(1, repaired.0[0].interiors().len());
+        assert!(repaired.0[1].interiors().is_empty());
+        assert!((plate.unsigned_area() - repaired.unsigned_area()).abs() < 1e-9);
+        for polygon in repaired.iter() {
+            assert!(validate(polygon, context.tolerance).is_empty());
+        }
+    }
+}
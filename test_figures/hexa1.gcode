@@ -0,0 +1,477 @@
+G21
+G90
+M3 S12000
+G0 Z5.000
+G0 X-0.150 Y0.000
+G1 Z-1.200 F200
+F800
+G1 X-2.639 Y1.437
+G1 X-2.639 Y0.744
+G1 X-0.750 Y-0.346
+G1 X-0.150 Y0.000
+G1 Z-2.400 F200
+F800
+G1 X-2.639 Y1.437
+G1 X-2.639 Y0.744
+G1 X-0.750 Y-0.346
+G1 X-0.150 Y0.000
+G1 Z-3.000 F200
+F800
+G1 X-2.639 Y1.437
+G1 X-2.639 Y0.744
+G1 X-0.750 Y-0.346
+G1 X-0.150 Y0.000
+G0 Z5.000
+G0 X-0.075 Y-0.130
+G1 Z-1.200 F200
+F800
+G1 X-2.564 Y-1.567
+G1 X-1.964 Y-1.913
+G1 X-0.075 Y-0.823
+G1 X-0.075 Y-0.130
+G1 Z-2.400 F200
+F800
+G1 X-2.564 Y-1.567
+G1 X-1.964 Y-1.913
+G1 X-0.075 Y-0.823
+G1 X-0.075 Y-0.130
+G1 Z-3.000 F200
+F800
+G1 X-2.564 Y-1.567
+G1 X-1.964 Y-1.913
+G1 X-0.075 Y-0.823
+G1 X-0.075 Y-0.130
+G0 Z5.000
+G0 X0.075 Y-0.130
+G1 Z-1.200 F200
+F800
+G1 X0.075 Y-3.004
+G1 X0.675 Y-2.658
+G1 X0.675 Y-0.476
+G1 X0.075 Y-0.130
+G1 Z-2.400 F200
+F800
+G1 X0.075 Y-3.004
+G1 X0.675 Y-2.658
+G1 X0.675 Y-0.476
+G1 X0.075 Y-0.130
+G1 Z-3.000 F200
+F800
+G1 X0.075 Y-3.004
+G1 X0.675 Y-2.658
+G1 X0.675 Y-0.476
+G1 X0.075 Y-0.130
+G0 Z5.000
+G0 X0.150 Y-0.000
+G1 Z-1.200 F200
+F800
+G1 X2.639 Y-1.437
+G1 X2.639 Y-0.744
+G1 X0.750 Y0.346
+G1 X0.150 Y-0.000
+G1 Z-2.400 F200
+F800
+G1 X2.639 Y-1.437
+G1 X2.639 Y-0.744
+G1 X0.750 Y0.346
+G1 X0.150 Y-0.000
+G1 Z-3.000 F200
+F800
+G1 X2.639 Y-1.437
+G1 X2.639 Y-0.744
+G1 X0.750 Y0.346
+G1 X0.150 Y-0.000
+G0 Z5.000
+G0 X0.456 Y0.350
+G1 Z-1.200 F200
+F800
+G1 X3.314 Y2.000
+G1 X2.548 Y2.442
+G1 X0.456 Y0.350
+G1 Z-2.400 F200
+F800
+G1 X3.314 Y2.000
+G1 X2.548 Y2.442
+G1 X0.456 Y0.350
+G1 Z-3.000 F200
+F800
+G1 X3.314 Y2.000
+G1 X2.548 Y2.442
+G1 X0.456 Y0.350
+G0 Z5.000
+G0 X0.350 Y0.456
+G1 Z-1.200 F200
+F800
+G1 X2.414 Y2.520
+G1 X1.760 Y2.898
+G1 X0.350 Y0.456
+G1 Z-2.400 F200
+F800
+G1 X2.414 Y2.520
+G1 X1.760 Y2.898
+G1 X0.350 Y0.456
+G1 Z-3.000 F200
+F800
+G1 X2.414 Y2.520
+G1 X1.760 Y2.898
+G1 X0.350 Y0.456
+G0 Z5.000
+G0 X0.220 Y0.531
+G1 Z-1.200 F200
+F800
+G1 X1.630 Y2.973
+G1 X0.975 Y3.350
+G1 X0.220 Y0.531
+G1 Z-2.400 F200
+F800
+G1 X1.630 Y2.973
+G1 X0.975 Y3.350
+G1 X0.220 Y0.531
+G1 Z-3.000 F200
+F800
+G1 X1.630 Y2.973
+G1 X0.975 Y3.350
+G1 X0.220 Y0.531
+G0 Z5.000
+G0 X0.075 Y0.570
+G1 Z-1.200 F200
+F800
+G1 X0.841 Y3.428
+G1 X0.075 Y3.870
+G1 X0.075 Y0.570
+G1 Z-2.400 F200
+F800
+G1 X0.841 Y3.428
+G1 X0.075 Y3.870
+G1 X0.075 Y0.570
+G1 Z-3.000 F200
+F800
+G1 X0.841 Y3.428
+G1 X0.075 Y3.870
+G1 X0.075 Y0.570
+G0 Z5.000
+G0 X-0.075 Y0.570
+G1 Z-1.200 F200
+F800
+G1 X-0.075 Y3.870
+G1 X-0.841 Y3.428
+G1 X-0.075 Y0.570
+G1 Z-2.400 F200
+F800
+G1 X-0.075 Y3.870
+G1 X-0.841 Y3.428
+G1 X-0.075 Y0.570
+G1 Z-3.000 F200
+F800
+G1 X-0.075 Y3.870
+G1 X-0.841 Y3.428
+G1 X-0.075 Y0.570
+G0 Z5.000
+G0 X-0.220 Y0.531
+G1 Z-1.200 F200
+F800
+G1 X-0.975 Y3.350
+G1 X-1.630 Y2.973
+G1 X-0.220 Y0.531
+G1 Z-2.400 F200
+F800
+G1 X-0.975 Y3.350
+G1 X-1.630 Y2.973
+G1 X-0.220 Y0.531
+G1 Z-3.000 F200
+F800
+G1 X-0.975 Y3.350
+G1 X-1.630 Y2.973
+G1 X-0.220 Y0.531
+G0 Z5.000
+G0 X-0.350 Y0.456
+G1 Z-1.200 F200
+F800
+G1 X-1.760 Y2.898
+G1 X-2.414 Y2.520
+G1 X-0.350 Y0.456
+G1 Z-2.400 F200
+F800
+G1 X-1.760 Y2.898
+G1 X-2.414 Y2.520
+G1 X-0.350 Y0.456
+G1 Z-3.000 F200
+F800
+G1 X-1.760 Y2.898
+G1 X-2.414 Y2.520
+G1 X-0.350 Y0.456
+G0 Z5.000
+G0 X-0.456 Y0.350
+G1 Z-1.200 F200
+F800
+G1 X-2.548 Y2.442
+G1 X-3.314 Y2.000
+G1 X-0.456 Y0.350
+G1 Z-2.400 F200
+F800
+G1 X-2.548 Y2.442
+G1 X-3.314 Y2.000
+G1 X-0.456 Y0.350
+G1 Z-3.000 F200
+F800
+G1 X-2.548 Y2.442
+G1 X-3.314 Y2.000
+G1 X-0.456 Y0.350
+G0 Z5.000
+G0 X-0.900 Y-0.433
+G1 Z-1.200 F200
+F800
+G1 X-1.500 Y-0.087
+G1 X-3.389 Y-1.177
+G1 X-3.389 Y-1.870
+G1 X-0.900 Y-0.433
+G1 Z-2.400 F200
+F800
+G1 X-1.500 Y-0.087
+G1 X-3.389 Y-1.177
+G1 X-3.389 Y-1.870
+G1 X-0.900 Y-0.433
+G1 Z-3.000 F200
+F800
+G1 X-1.500 Y-0.087
+G1 X-3.389 Y-1.177
+G1 X-3.389 Y-1.870
+G1 X-0.900 Y-0.433
+G0 Z5.000
+G0 X-1.650 Y0.000
+G1 Z-1.200 F200
+F800
+G1 X-2.639 Y0.571
+G1 X-2.639 Y-0.571
+G1 X-1.650 Y0.000
+G1 Z-2.400 F200
+F800
+G1 X-2.639 Y0.571
+G1 X-2.639 Y-0.571
+G1 X-1.650 Y0.000
+G1 Z-3.000 F200
+F800
+G1 X-2.639 Y0.571
+G1 X-2.639 Y-0.571
+G1 X-1.650 Y0.000
+G0 Z5.000
+G0 X-2.789 Y-0.658
+G1 Z-1.200 F200
+F800
+G1 X-2.789 Y1.524
+G1 X-3.389 Y1.870
+G1 X-3.389 Y-1.004
+G1 X-2.789 Y-0.658
+G1 Z-2.400 F200
+F800
+G1 X-2.789 Y1.524
+G1 X-3.389 Y1.870
+G1 X-3.389 Y-1.004
+G1 X-2.789 Y-0.658
+G1 Z-3.000 F200
+F800
+G1 X-2.789 Y1.524
+G1 X-3.389 Y1.870
+G1 X-3.389 Y-1.004
+G1 X-2.789 Y-0.658
+G0 Z5.000
+G0 X-2.714 Y-1.654
+G1 Z-1.200 F200
+F800
+G1 X-3.314 Y-2.000
+G1 X-0.825 Y-3.437
+G1 X-0.825 Y-2.744
+G1 X-2.714 Y-1.654
+G1 Z-2.400 F200
+F800
+G1 X-3.314 Y-2.000
+G1 X-0.825 Y-3.437
+G1 X-0.825 Y-2.744
+G1 X-2.714 Y-1.654
+G1 Z-3.000 F200
+F800
+G1 X-3.314 Y-2.000
+G1 X-0.825 Y-3.437
+G1 X-0.825 Y-2.744
+G1 X-2.714 Y-1.654
+G0 Z5.000
+G0 X-1.814 Y-2.000
+G1 Z-1.200 F200
+F800
+G1 X-0.825 Y-2.571
+G1 X-0.825 Y-1.429
+G1 X-1.814 Y-2.000
+G1 Z-2.400 F200
+F800
+G1 X-0.825 Y-2.571
+G1 X-0.825 Y-1.429
+G1 X-1.814 Y-2.000
+G1 Z-3.000 F200
+F800
+G1 X-0.825 Y-2.571
+G1 X-0.825 Y-1.429
+G1 X-1.814 Y-2.000
+G0 Z5.000
+G0 X-0.675 Y-1.342
+G1 Z-1.200 F200
+F800
+G1 X-0.675 Y-3.524
+G1 X-0.075 Y-3.870
+G1 X-0.075 Y-0.996
+G1 X-0.675 Y-1.342
+G1 Z-2.400 F200
+F800
+G1 X-0.675 Y-3.524
+G1 X-0.075 Y-3.870
+G1 X-0.075 Y-0.996
+G1 X-0.675 Y-1.342
+G1 Z-3.000 F200
+F800
+G1 X-0.675 Y-3.524
+G1 X-0.075 Y-3.870
+G1 X-0.075 Y-0.996
+G1 X-0.675 Y-1.342
+G0 Z5.000
+G0 X0.825 Y-1.256
+G1 Z-1.200 F200
+F800
+G1 X2.714 Y-2.346
+G1 X3.314 Y-2.000
+G1 X0.825 Y-0.563
+G1 X0.825 Y-1.256
+G1 Z-2.400 F200
+F800
+G1 X2.714 Y-2.346
+G1 X3.314 Y-2.000
+G1 X0.825 Y-0.563
+G1 X0.825 Y-1.256
+G1 Z-3.000 F200
+F800
+G1 X2.714 Y-2.346
+G1 X3.314 Y-2.000
+G1 X0.825 Y-0.563
+G1 X0.825 Y-1.256
+G0 Z5.000
+G0 X0.825 Y-1.429
+G1 Z-1.200 F200
+F800
+G1 X0.825 Y-2.571
+G1 X1.814 Y-2.000
+G1 X0.825 Y-1.429
+G1 Z-2.400 F200
+F800
+G1 X0.825 Y-2.571
+G1 X1.814 Y-2.000
+G1 X0.825 Y-1.429
+G1 Z-3.000 F200
+F800
+G1 X0.825 Y-2.571
+G1 X1.814 Y-2.000
+G1 X0.825 Y-1.429
+G0 Z5.000
+G0 X1.964 Y-2.087
+G1 Z-1.200 F200
+F800
+G1 X0.075 Y-3.177
+G1 X0.075 Y-3.870
+G1 X2.564 Y-2.433
+G1 X1.964 Y-2.087
+G1 Z-2.400 F200
+F800
+G1 X0.075 Y-3.177
+G1 X0.075 Y-3.870
+G1 X2.564 Y-2.433
+G1 X1.964 Y-2.087
+G1 Z-3.000 F200
+F800
+G1 X0.075 Y-3.177
+G1 X0.075 Y-3.870
+G1 X2.564 Y-2.433
+G1 X1.964 Y-2.087
+G0 Z5.000
+G0 X2.789 Y-1.524
+G1 Z-1.200 F200
+F800
+G1 X3.389 Y-1.870
+G1 X3.389 Y1.004
+G1 X2.789 Y0.658
+G1 X2.789 Y-1.524
+G1 Z-2.400 F200
+F800
+G1 X3.389 Y-1.870
+G1 X3.389 Y1.004
+G1 X2.789 Y0.658
+G1 X2.789 Y-1.524
+G1 Z-3.000 F200
+F800
+G1 X3.389 Y-1.870
+G1 X3.389 Y1.004
+G1 X2.789 Y0.658
+G1 X2.789 Y-1.524
+G0 Z5.000
+G0 X2.639 Y-0.571
+G1 Z-1.200 F200
+F800
+G1 X2.639 Y0.571
+G1 X1.650 Y-0.000
+G1 X2.639 Y-0.571
+G1 Z-2.400 F200
+F800
+G1 X2.639 Y0.571
+G1 X1.650 Y-0.000
+G1 X2.639 Y-0.571
+G1 Z-3.000 F200
+F800
+G1 X2.639 Y0.571
+G1 X1.650 Y-0.000
+G1 X2.639 Y-0.571
+G0 Z5.000
+G0 X1.500 Y0.087
+G1 Z-1.200 F200
+F800
+G1 X3.389 Y1.177
+G1 X3.389 Y1.870
+G1 X0.900 Y0.433
+G1 X1.500 Y0.087
+G1 Z-2.400 F200
+F800
+G1 X3.389 Y1.177
+G1 X3.389 Y1.870
+G1 X0.900 Y0.433
+G1 X1.500 Y0.087
+G1 Z-3.000 F200
+F800
+G1 X3.389 Y1.177
+G1 X3.389 Y1.870
+G1 X0.900 Y0.433
+G1 X1.500 Y0.087
+G0 Z5.000
+G0 X3.464 Y2.000
+G1 Z-1.200 F200
+F800
+G1 X0.000 Y4.000
+G1 X-3.464 Y2.000
+G1 X-3.464 Y-2.000
+G1 X0.000 Y-4.000
+G1 X3.464 Y-2.000
+G1 X3.464 Y2.000
+G1 Z-2.400 F200
+F800
+G1 X0.000 Y4.000
+G1 X-3.464 Y2.000
+G1 X-3.464 Y-2.000
+G1 X0.000 Y-4.000
+G1 X3.464 Y-2.000
+G1 X3.464 Y2.000
+G1 Z-3.000 F200
+F800
+G1 X0.000 Y4.000
+G1 X-3.464 Y2.000
+G1 X-3.464 Y-2.000
+G1 X0.000 Y-4.000
+G1 X3.464 Y-2.000
+G1 X3.464 Y2.000
+G0 Z5.000
+M5
+G0 X0 Y0
+M2